@@ -0,0 +1,122 @@
+//! Bond helpers.
+
+use crate::{error::Error, fdb::MacAddr, parse, OvsUnixCtl, Result};
+
+/// A bond, as shown by "bond/show".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BondShow {
+    /// Name of the bond.
+    pub name: String,
+    /// Bonding mode, e.g: `balance-slb` or `active-backup`.
+    pub bond_mode: String,
+    /// LACP status, e.g: `off` or `negotiated`.
+    pub lacp_status: String,
+    /// Name of the active member (a.k.a slave), if any.
+    pub active_slave: Option<String>,
+    /// Members (a.k.a slaves) of the bond.
+    pub slaves: Vec<BondSlave>,
+}
+
+/// A bond, as listed by "bond/list".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BondEntry {
+    /// Name of the bond.
+    pub name: String,
+    /// Bonding mode, e.g: `balance-slb` or `active-backup`.
+    pub bond_type: String,
+    /// Recirculation id used by the bond, `None` if not using recirculation or not reported.
+    pub recirc_id: Option<u32>,
+    /// Names of the members (a.k.a slaves) of the bond.
+    pub slaves: Vec<String>,
+}
+
+/// A bond member (a.k.a slave).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BondSlave {
+    /// Name of the member.
+    pub name: String,
+    /// Whether the member is enabled.
+    pub enabled: bool,
+    /// Whether the member may be enabled, e.g: its carrier is up.
+    pub may_enable: bool,
+    /// Hash buckets assigned to the member and their load in kB, as `(hash, load)` tuples.
+    pub hashes: Vec<(u32, u64)>,
+}
+
+impl OvsUnixCtl {
+    /// Runs the "bond/list" command and returns the bonds.
+    pub fn bond_list(&mut self) -> Result<Vec<BondEntry>> {
+        // The header is printed even without bonds.
+        parse::bond::list(&self.run_output("bond/list", None)?)
+    }
+
+    /// Runs the "bond/show" command and returns the details of the given bond.
+    pub fn bond_show(&mut self, bond: &str) -> Result<BondShow> {
+        parse::bond::show(&self.run_output("bond/show", Some(&[bond]))?)
+    }
+
+    /// Runs the "bond/hash" command and returns the hash bucket the given MAC address (and
+    /// optional VLAN and hash basis) maps to.
+    pub fn bond_hash(&mut self, mac: &str, vlan: Option<u16>, basis: Option<u32>) -> Result<u32> {
+        mac.parse::<MacAddr>()
+            .map_err(|_| Error::InvalidArgument(format!("invalid MAC address: {mac}")))?;
+
+        let mut params = vec![mac.to_string()];
+        match (vlan, basis) {
+            (Some(vlan), Some(basis)) => params.extend([vlan.to_string(), basis.to_string()]),
+            (Some(vlan), None) => params.push(vlan.to_string()),
+            // The basis is the third positional argument, the VLAN has to be set too.
+            (None, Some(basis)) => params.extend(["0".to_string(), basis.to_string()]),
+            (None, None) => (),
+        }
+        let params: Vec<&str> = params.iter().map(String::as_str).collect();
+
+        parse::bond::hash(&self.run_output("bond/hash", Some(&params))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeServer;
+
+    #[test]
+    fn bond_hash() {
+        let server = FakeServer::new("bond_hash", |_, _| Ok("42".to_string()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert!(matches!(
+            ovs.bond_hash("not-a-mac", None, None),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert_eq!(ovs.bond_hash("52:54:00:12:34:56", None, None).unwrap(), 42);
+        assert_eq!(
+            ovs.bond_hash("52:54:00:12:34:56", Some(10), Some(3))
+                .unwrap(),
+            42
+        );
+        assert_eq!(
+            ovs.bond_hash("52:54:00:12:34:56", None, Some(3)).unwrap(),
+            42
+        );
+        drop(ovs);
+
+        let mac = "52:54:00:12:34:56".to_string();
+        assert_eq!(
+            server.requests(),
+            vec![
+                ("bond/hash".to_string(), vec![mac.clone()]),
+                (
+                    "bond/hash".to_string(),
+                    vec![mac.clone(), "10".to_string(), "3".to_string()]
+                ),
+                (
+                    "bond/hash".to_string(),
+                    vec![mac, "0".to_string(), "3".to_string()]
+                ),
+            ]
+        );
+    }
+}
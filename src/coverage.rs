@@ -0,0 +1,68 @@
+//! Coverage counters helpers.
+
+use std::collections::HashMap;
+
+use crate::{parse, OvsUnixCtl, Result};
+
+/// A coverage counter, i.e: the number of times an event occurred in the daemon.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CoverageCounter {
+    /// Name of the event.
+    pub name: String,
+    /// Average rate, per second, over the last 5 seconds.
+    pub last_rate: f64,
+    /// Average rate, per second, over the last minute, if reported.
+    pub minute_rate: Option<f64>,
+    /// Average rate, per second, over the last hour, if reported.
+    pub hour_rate: Option<f64>,
+    /// Number of times the event occurred since the daemon started.
+    pub total: u64,
+}
+
+impl OvsUnixCtl {
+    /// Runs the "coverage/show" command and returns the coverage counters.
+    pub fn coverage_show(&mut self) -> Result<Vec<CoverageCounter>> {
+        parse::coverage::show(&self.run_output("coverage/show", None)?)
+    }
+
+    /// Runs the "coverage/show" command and returns the coverage counters that were hit at least
+    /// once, indexed by name.
+    pub fn coverage_active(&mut self) -> Result<HashMap<String, CoverageCounter>> {
+        Ok(active(self.coverage_show()?))
+    }
+}
+
+/// Keeps the counters with a non-zero total and indexes them by name.
+fn active(counters: Vec<CoverageCounter>) -> HashMap<String, CoverageCounter> {
+    counters
+        .into_iter()
+        .filter(|c| c.total != 0)
+        .map(|c| (c.name.clone(), c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_counters() {
+        let counter = |name: &str, total| CoverageCounter {
+            name: name.to_string(),
+            total,
+            ..Default::default()
+        };
+        let counters = active(vec![
+            counter("bridge_reconfigure", 1),
+            counter("dpif_flow_put", 0),
+            counter("netlink_sent", 42),
+            counter("ofproto_flush", 0),
+        ]);
+
+        let mut names: Vec<&str> = counters.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, vec!["bridge_reconfigure", "netlink_sent"]);
+        assert_eq!(counters["netlink_sent"].total, 42);
+    }
+}
@@ -0,0 +1,515 @@
+//! Datapath (dpctl) helpers.
+
+use std::{collections::HashMap, net::IpAddr, slice, time::Duration};
+
+use crate::{parse, OvsUnixCtl, Result};
+
+/// The kind of a datapath flow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FlowKind {
+    /// A flow matching some fields with a mask (a.k.a megaflow).
+    Megaflow,
+    /// A flow matching all its fields exactly.
+    #[default]
+    ExactMatch,
+}
+
+/// A datapath flow.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Flow {
+    /// The flow match, as printed by OVS.
+    #[cfg_attr(feature = "serde", serde(rename = "match"))]
+    pub match_: String,
+    /// Number of packets that matched the flow.
+    pub packets: u64,
+    /// Number of bytes that matched the flow.
+    pub bytes: u64,
+    /// Time since the flow was last used, `None` if it was never used.
+    pub used: Option<Duration>,
+    /// The flow actions, as printed by OVS.
+    pub actions: String,
+    /// Kind of flow. It is inferred from the match: a flow having no masked field is considered
+    /// to be an exact match.
+    pub kind: FlowKind,
+}
+
+/// Flow statistic to rank flows by, see [`OvsUnixCtl::top_flows`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlowMetric {
+    /// Number of packets that matched the flow.
+    Packets,
+    /// Number of bytes that matched the flow.
+    Bytes,
+}
+
+/// A datapath flow, without its statistics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BriefFlow {
+    /// The flow match, as printed by OVS.
+    #[cfg_attr(feature = "serde", serde(rename = "match"))]
+    pub match_: String,
+    /// The flow actions, as printed by OVS.
+    pub actions: String,
+}
+
+impl Flow {
+    /// Returns the fields of the flow match as `(field, value, mask)` tuples.
+    ///
+    /// Nested fields are named after their parent, e.g: `ipv4(src=10.0.0.1)` gives the
+    /// `ipv4.src` field. The mask is only set when the field carries one, e.g:
+    /// `ipv4(dst=10.0.0.0/255.255.255.0)` gives
+    /// `("ipv4.dst", "10.0.0.0", Some("255.255.255.0"))`.
+    pub fn match_fields(&self) -> Vec<(String, String, Option<String>)> {
+        parse::dpctl::match_fields(&self.match_)
+    }
+}
+
+/// A datapath, as shown by "dpctl/show".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Datapath {
+    /// Name of the datapath, e.g: `system@ovs-system`.
+    pub name: String,
+    /// Flow table lookup statistics.
+    pub lookups: DpLookups,
+    /// Megaflow mask statistics.
+    pub masks: DpMasks,
+    /// Number of flows in the datapath.
+    pub flows: u64,
+    /// Number of ports of the datapath.
+    pub n_ports: u64,
+    /// Ports of the datapath.
+    pub ports: Vec<DpPort>,
+}
+
+/// Datapath flow table lookup statistics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DpLookups {
+    /// Number of packets that matched an existing flow.
+    pub hit: u64,
+    /// Number of packets that didn't match any flow and were sent to userspace.
+    pub missed: u64,
+    /// Number of packets that didn't match any flow and were dropped before reaching userspace.
+    pub lost: u64,
+}
+
+/// Datapath megaflow mask statistics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DpMasks {
+    /// Number of masks visited by lookups that matched a flow.
+    pub hit: u64,
+    /// Number of masks in the datapath.
+    pub total: u64,
+}
+
+/// A datapath port.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DpPort {
+    /// Port number.
+    pub port_no: u32,
+    /// Port name.
+    pub name: String,
+    /// Port type (and configuration), if not a regular system port, e.g: `internal`.
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub type_: Option<String>,
+    /// Port statistics, if reported.
+    pub stats: Option<DpPortStats>,
+}
+
+/// Statistics of a datapath port. Counters the port doesn't support are reported as 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DpPortStats {
+    /// Number of received packets.
+    pub rx_packets: u64,
+    /// Number of received bytes.
+    pub rx_bytes: u64,
+    /// Number of received packets which were dropped.
+    pub rx_dropped: u64,
+    /// Number of receive errors.
+    pub rx_errors: u64,
+    /// Number of transmitted packets.
+    pub tx_packets: u64,
+    /// Number of transmitted bytes.
+    pub tx_bytes: u64,
+    /// Number of packets which were dropped instead of being transmitted.
+    pub tx_dropped: u64,
+    /// Number of transmit errors.
+    pub tx_errors: u64,
+}
+
+/// Statistics summed across all datapaths.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DatapathTotals {
+    /// Total number of flows.
+    pub flows: u64,
+    /// Total number of lookup hits.
+    pub hit: u64,
+    /// Total number of lookup misses.
+    pub missed: u64,
+    /// Total number of lost packets.
+    pub lost: u64,
+    /// Total number of ports.
+    pub n_ports: u64,
+}
+
+impl<'a> FromIterator<&'a Datapath> for DatapathTotals {
+    fn from_iter<I: IntoIterator<Item = &'a Datapath>>(iter: I) -> DatapathTotals {
+        iter.into_iter()
+            .fold(DatapathTotals::default(), |mut totals, dp| {
+                totals.flows += dp.flows;
+                totals.hit += dp.lookups.hit;
+                totals.missed += dp.lookups.missed;
+                totals.lost += dp.lookups.lost;
+                totals.n_ports += dp.n_ports;
+                totals
+            })
+    }
+}
+
+/// Connection tracking statistics, as shown by "dpctl/ct-stats-show".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CtStats {
+    /// Total number of connection tracking entries.
+    pub total: u64,
+    /// Number of entries per protocol, e.g: `TCP`. Protocols without entries are not reported.
+    pub protocols: HashMap<String, u64>,
+}
+
+/// A connection tracking entry, as shown by "dpctl/dump-conntrack".
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CtEntry {
+    /// Protocol of the connection, e.g: `tcp`, `udp` or `icmp`.
+    pub protocol: String,
+    /// Tuple of the original direction.
+    pub orig: CtTuple,
+    /// Tuple of the reply direction.
+    pub reply: CtTuple,
+    /// State of the connection, e.g: `ESTABLISHED` or `TIME_WAIT`, for protocols having one.
+    pub state: Option<String>,
+    /// Mark of the connection, if set.
+    pub mark: Option<u32>,
+    /// Zone of the connection, if not the default one.
+    pub zone: Option<u16>,
+    /// Time in seconds before the entry expires, if reported.
+    pub timeout: Option<u32>,
+}
+
+/// One direction of a connection tracking entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CtTuple {
+    /// Source address.
+    pub src: IpAddr,
+    /// Destination address.
+    pub dst: IpAddr,
+    /// Source port, for protocols having ports.
+    pub sport: Option<u16>,
+    /// Destination port, for protocols having ports.
+    pub dport: Option<u16>,
+}
+
+/// Summary of the connection tracking table usage.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CtSummary {
+    /// Number of connection tracking entries.
+    pub total: u64,
+    /// Maximum number of connection tracking entries, 0 if unlimited.
+    pub max: u64,
+    /// Percentage of the table in use, `None` if unlimited.
+    pub pct_used: Option<f64>,
+}
+
+impl CtSummary {
+    /// Builds a summary from the number of entries and the maximum number of entries.
+    pub fn new(total: u64, max: u64) -> CtSummary {
+        CtSummary {
+            total,
+            max,
+            pct_used: (max != 0).then(|| total as f64 * 100.0 / max as f64),
+        }
+    }
+}
+
+/// Builder of a set of conntrack configuration changes.
+///
+/// Obtained through [`OvsUnixCtl::ct_config`], it issues the needed commands when applied.
+#[derive(Debug)]
+pub struct CtConfigBuilder<'a> {
+    unixctl: &'a mut OvsUnixCtl,
+    dp: Option<String>,
+    tcp_seq_check: Option<bool>,
+    maxconns: Option<u32>,
+}
+
+impl CtConfigBuilder<'_> {
+    /// Sets the datapath to configure. If not set, the only datapath is configured.
+    pub fn dp(mut self, dp: &str) -> Self {
+        self.dp = Some(dp.to_string());
+        self
+    }
+
+    /// Enables or disables TCP sequence number checking.
+    pub fn tcp_seq_check(mut self, enabled: bool) -> Self {
+        self.tcp_seq_check = Some(enabled);
+        self
+    }
+
+    /// Sets the maximum number of connection tracking entries.
+    pub fn maxconns(mut self, maxconns: u32) -> Self {
+        self.maxconns = Some(maxconns);
+        self
+    }
+
+    /// Applies the configuration, issuing the commands one after the other. Stops and returns
+    /// the first error encountered.
+    pub fn apply(self) -> Result<()> {
+        let dp: Vec<&str> = self.dp.iter().map(String::as_str).collect();
+
+        if let Some(enabled) = self.tcp_seq_check {
+            let cmd = match enabled {
+                true => "dpctl/ct-enable-tcp-seq-chk",
+                false => "dpctl/ct-disable-tcp-seq-chk",
+            };
+            self.unixctl.run(cmd, Some(&dp))?;
+        }
+
+        if let Some(maxconns) = self.maxconns {
+            let maxconns = maxconns.to_string();
+            let params: Vec<&str> = dp.iter().copied().chain([maxconns.as_str()]).collect();
+            self.unixctl.run("dpctl/ct-set-maxconns", Some(&params))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl OvsUnixCtl {
+    /// Runs the "dpctl/show -s" command and returns the datapaths and their statistics,
+    /// including the ones of their ports.
+    pub fn dpctl_show(&mut self) -> Result<Vec<Datapath>> {
+        parse::dpctl::show(&self.run_output("dpctl/show", Some(&["-s"]))?)
+    }
+
+    /// Returns the flow, lookup and port statistics summed across all datapaths.
+    ///
+    /// Statistics are retrieved using [`OvsUnixCtl::dpctl_show`], as "dpif/show" doesn't report
+    /// flow counts nor lost packets.
+    pub fn dpif_totals(&mut self) -> Result<DatapathTotals> {
+        Ok(self.dpctl_show()?.iter().collect())
+    }
+
+    /// Returns the number of flows in the datapath (or the only datapath if `None`), without
+    /// dumping them.
+    ///
+    /// OVS has no dedicated command to count the datapath flows, the count is read from the
+    /// "flows:" field of the "dpctl/show" command output (which is available in all supported
+    /// versions).
+    pub fn flow_count(&mut self, dp: Option<&str>) -> Result<u64> {
+        let response = self.run_output("dpctl/show", dp.as_ref().map(slice::from_ref))?;
+        parse::dpctl::flow_count(&response)
+    }
+
+    /// Returns a builder to change several conntrack settings at once.
+    ///
+    /// ```no_run
+    /// # let mut unixctl = ovs_unixctl::OvsUnixCtl::new(None).unwrap();
+    /// unixctl
+    ///     .ct_config()
+    ///     .tcp_seq_check(false)
+    ///     .maxconns(100000)
+    ///     .apply()
+    ///     .unwrap();
+    /// ```
+    pub fn ct_config(&mut self) -> CtConfigBuilder<'_> {
+        CtConfigBuilder {
+            unixctl: self,
+            dp: None,
+            tcp_seq_check: None,
+            maxconns: None,
+        }
+    }
+
+    /// Runs the "dpctl/ct-stats-show" command and returns the connection tracking statistics.
+    pub fn ct_stats_show(&mut self) -> Result<CtStats> {
+        parse::dpctl::ct_stats(&self.run_output("dpctl/ct-stats-show", None)?)
+    }
+
+    /// Runs the "dpctl/ct-get-maxconns" command and returns the maximum number of connection
+    /// tracking entries.
+    pub fn ct_get_maxconns(&mut self) -> Result<u32> {
+        parse::dpctl::ct_maxconns(&self.run_output("dpctl/ct-get-maxconns", None)?)
+    }
+
+    /// Returns the connection tracking table usage, combining [`OvsUnixCtl::ct_stats_show`] and
+    /// [`OvsUnixCtl::ct_get_maxconns`].
+    pub fn ct_summary(&mut self) -> Result<CtSummary> {
+        let total = self.ct_stats_show()?.total;
+        Ok(CtSummary::new(total, self.ct_get_maxconns()?.into()))
+    }
+
+    /// Runs the "dpctl/dump-conntrack" command and returns the connection tracking entries of the
+    /// given zone, or of all zones if `None`.
+    pub fn dump_conntrack(&mut self, zone: Option<u16>) -> Result<Vec<CtEntry>> {
+        let zone = zone.map(|zone| format!("zone={zone}"));
+        let mut params = vec!["-s"];
+        params.extend(zone.as_deref());
+        let response = self.run("dpctl/dump-conntrack", Some(&params))?;
+        parse::dpctl::conntrack(&response.unwrap_or_default())
+    }
+
+    /// Runs the "dpctl/dump-flows" command and returns the flows of the datapath (or the only
+    /// datapath if `None`).
+    pub fn dump_flows(&mut self, dp: Option<&str>) -> Result<Vec<Flow>> {
+        let response = self.dpctl_dump_flows(dp)?;
+        parse::dpctl::flows(&response)
+    }
+
+    /// Runs the "dpif/dump-flows" command and returns the datapath flows installed on behalf of
+    /// the bridge.
+    pub fn dpif_dump_flows(&mut self, bridge: &str) -> Result<Vec<Flow>> {
+        let response = self.run("dpif/dump-flows", Some(&[bridge]))?;
+        parse::dpctl::dpif_flows(&response.unwrap_or_default())
+    }
+
+    /// Runs the "dpctl/dump-flows" command and returns the `n` flows of the datapath (or the only
+    /// datapath if `None`) with the most traffic, sorted in descending order.
+    ///
+    /// Only `n` flows are kept while parsing the output, which bounds the memory used on large
+    /// flow tables to the size of the output itself.
+    pub fn top_flows(&mut self, dp: Option<&str>, n: usize, by: FlowMetric) -> Result<Vec<Flow>> {
+        let response = self.dpctl_dump_flows(dp)?;
+        parse::dpctl::top_flows(&response, n, by)
+    }
+
+    /// Runs the "dpctl/dump-flows" command and returns the matches and actions of the flows of
+    /// the datapath (or the only datapath if `None`).
+    ///
+    /// OVS has no statistics-less form of the command: the full output is retrieved but the
+    /// statistics are skipped instead of being parsed, which is cheaper on large flow tables.
+    pub fn dump_flows_brief(&mut self, dp: Option<&str>) -> Result<Vec<BriefFlow>> {
+        let response = self.dpctl_dump_flows(dp)?;
+        parse::dpctl::flows_brief(&response)
+    }
+
+    /// Runs the "dpctl/dump-flows" command on the datapath (or the only datapath if `None`). An
+    /// empty output means the datapath has no flows.
+    fn dpctl_dump_flows(&mut self, dp: Option<&str>) -> Result<String> {
+        Ok(self
+            .run("dpctl/dump-flows", dp.as_ref().map(slice::from_ref))?
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeServer;
+
+    #[test]
+    fn ct_summary() {
+        let summary = CtSummary::new(2500, 10000);
+        assert_eq!((summary.total, summary.max), (2500, 10000));
+        assert_eq!(summary.pct_used, Some(25.0));
+        assert_eq!(CtSummary::new(2500, 0).pct_used, None);
+        assert_eq!(CtSummary::new(0, 10000).pct_used, Some(0.0));
+    }
+
+    #[test]
+    fn datapath_totals() {
+        let dp = |name: &str, flows, hit, missed, lost, n_ports| Datapath {
+            name: name.to_string(),
+            lookups: DpLookups { hit, missed, lost },
+            flows,
+            n_ports,
+            ..Default::default()
+        };
+        let datapaths = [
+            dp("system@ovs-system", 10, 100, 20, 1, 4),
+            dp("netdev@ovs-netdev", 5, 50, 2, 0, 3),
+        ];
+
+        assert_eq!(
+            datapaths.iter().collect::<DatapathTotals>(),
+            DatapathTotals {
+                flows: 15,
+                hit: 150,
+                missed: 22,
+                lost: 1,
+                n_ports: 7,
+            }
+        );
+        assert_eq!(
+            [].iter().collect::<DatapathTotals>(),
+            DatapathTotals::default()
+        );
+    }
+
+    #[test]
+    fn ct_config_apply() {
+        let server = FakeServer::new("ct_config_apply", |_, _| Ok(String::default()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        ovs.ct_config().apply().unwrap();
+        ovs.ct_config()
+            .tcp_seq_check(false)
+            .maxconns(1000)
+            .apply()
+            .unwrap();
+        ovs.ct_config()
+            .dp("netdev@ovs-netdev")
+            .maxconns(10)
+            .tcp_seq_check(true)
+            .apply()
+            .unwrap();
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![
+                ("dpctl/ct-disable-tcp-seq-chk".to_string(), vec![]),
+                (
+                    "dpctl/ct-set-maxconns".to_string(),
+                    vec!["1000".to_string()]
+                ),
+                (
+                    "dpctl/ct-enable-tcp-seq-chk".to_string(),
+                    vec!["netdev@ovs-netdev".to_string()]
+                ),
+                (
+                    "dpctl/ct-set-maxconns".to_string(),
+                    vec!["netdev@ovs-netdev".to_string(), "10".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn ct_config_apply_error() {
+        let server = FakeServer::new("ct_config_apply_error", |_, _| {
+            Err("datapath not found".to_string())
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert!(matches!(
+            ovs.ct_config().tcp_seq_check(true).maxconns(10).apply(),
+            Err(crate::Error::Command { .. })
+        ));
+        drop(ovs);
+
+        // The first error stops the sequence.
+        assert_eq!(
+            server.requests(),
+            vec![("dpctl/ct-enable-tcp-seq-chk".to_string(), vec![])]
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Userspace datapath (dpif-netdev) helpers.
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{error::Error, parse, OvsUnixCtl, Result};
+
+/// Bounds of the window, in seconds, accepted by "dpif-netdev/pmd-rxq-show -secs".
+const PMD_RXQ_WINDOW_SECS: std::ops::RangeInclusive<u32> = 1..=60;
+
+/// Information about a dpcls subtable lookup implementation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SubtableInfo {
+    /// Priority of the implementation. The one with the highest priority is used.
+    pub prio: u32,
+    /// Name of the implementation.
+    pub name: String,
+    /// Number of subtables using this implementation.
+    pub usage: u64,
+}
+
+/// Receive queues polled by a PMD thread.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PmdRxq {
+    /// NUMA node of the PMD thread.
+    pub numa_id: u32,
+    /// Core the PMD thread runs on.
+    pub core_id: u32,
+    /// Whether the PMD thread is isolated, i.e: only polls the queues pinned to it.
+    pub isolated: bool,
+    /// Receive queues polled by the PMD thread.
+    pub rxqs: Vec<RxqUsage>,
+}
+
+/// A receive queue and its PMD usage.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RxqUsage {
+    /// Port the queue belongs to.
+    pub port: String,
+    /// Queue id.
+    pub queue: u32,
+    /// Whether the queue is enabled.
+    pub enabled: bool,
+    /// Percentage of the PMD processing cycles used by the queue, `None` if not available.
+    pub usage: Option<u32>,
+    /// Whether the queue is polled by a PMD thread running on a different NUMA node than its
+    /// port, which hurts performance. `None` if the NUMA node of the port is unknown, see
+    /// [`PmdRxq::set_port_numa`].
+    pub cross_numa: Option<bool>,
+}
+
+/// Statistics of a PMD thread, as shown by "dpif-netdev/pmd-stats-show".
+///
+/// Packets are counted each time they go through the datapath, i.e: a recirculated packet is
+/// looked up (and counted as a hit or a miss) again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PmdStats {
+    /// NUMA node of the PMD thread, `None` for the main thread.
+    pub numa_id: Option<u32>,
+    /// Core the PMD thread runs on, `None` for the main thread.
+    pub core_id: Option<u32>,
+    /// Number of packets received.
+    pub packets_received: u64,
+    /// Number of packet recirculations.
+    pub recirculations: u64,
+    /// Number of exact match cache hits.
+    pub emc_hits: u64,
+    /// Number of signature match cache hits.
+    pub smc_hits: u64,
+    /// Number of megaflow (dpcls) hits.
+    pub megaflow_hits: u64,
+    /// Number of misses resulting in a successful upcall.
+    pub misses: u64,
+    /// Number of misses whose upcall failed, i.e: lost packets.
+    pub lost: u64,
+    /// Number of cycles spent polling without receiving packets.
+    pub idle_cycles: u64,
+    /// Number of cycles spent processing packets.
+    pub busy_cycles: u64,
+}
+
+impl PmdStats {
+    /// Number of datapath passes, i.e: received packets and recirculations.
+    pub fn passes(&self) -> u64 {
+        self.packets_received + self.recirculations
+    }
+
+    /// Ratio of datapath passes hitting the exact match cache, `None` if there was none.
+    pub fn emc_hit_ratio(&self) -> Option<f64> {
+        self.pass_ratio(self.emc_hits)
+    }
+
+    /// Ratio of datapath passes hitting the signature match cache, `None` if there was none.
+    pub fn smc_hit_ratio(&self) -> Option<f64> {
+        self.pass_ratio(self.smc_hits)
+    }
+
+    /// Ratio of datapath passes hitting a megaflow, `None` if there was none.
+    pub fn megaflow_hit_ratio(&self) -> Option<f64> {
+        self.pass_ratio(self.megaflow_hits)
+    }
+
+    /// Ratio of datapath passes missing all caches, whether their upcall succeeded or not,
+    /// `None` if there was none.
+    pub fn miss_ratio(&self) -> Option<f64> {
+        self.pass_ratio(self.misses + self.lost)
+    }
+
+    /// Ratio of cycles spent processing packets, `None` if no cycles were counted.
+    pub fn busy_ratio(&self) -> Option<f64> {
+        match self.idle_cycles + self.busy_cycles {
+            0 => None,
+            total => Some(self.busy_cycles as f64 / total as f64),
+        }
+    }
+
+    fn pass_ratio(&self, count: u64) -> Option<f64> {
+        match self.passes() {
+            0 => None,
+            passes => Some(count as f64 / passes as f64),
+        }
+    }
+}
+
+impl PmdRxq {
+    /// Sets whether each queue is polled across NUMA nodes, given the NUMA node of the ports.
+    ///
+    /// OVS doesn't report the NUMA node of the ports along with the queues; for DPDK ports it
+    /// can be found in the `numa_id` key of the `status` column of the OVSDB Interface table.
+    /// Queues of ports missing from `port_numa` are left as `None`.
+    pub fn set_port_numa(&mut self, port_numa: &HashMap<String, u32>) {
+        for rxq in self.rxqs.iter_mut() {
+            rxq.cross_numa = port_numa.get(&rxq.port).map(|numa| *numa != self.numa_id);
+        }
+    }
+}
+
+impl OvsUnixCtl {
+    /// Runs the "dpif-netdev/pmd-rxq-show" command and returns the receive queues polled by each
+    /// PMD thread.
+    pub fn pmd_rxq_show(&mut self) -> Result<Vec<PmdRxq>> {
+        parse::dpif_netdev::pmd_rxq_show(&self.run_output("dpif-netdev/pmd-rxq-show", None)?)
+    }
+
+    /// Runs the "dpif-netdev/pmd-rxq-show" command and returns the receive queues polled by each
+    /// PMD thread, with their usage computed over the last `secs` seconds instead of the default
+    /// window.
+    ///
+    /// The window must be between 1 and 60 seconds; OVS rounds it up to a multiple of its
+    /// measurement interval. Requires OVS >= 3.1, older versions reject the argument and a
+    /// [`Error::Command`] is returned.
+    pub fn pmd_rxq_show_window(&mut self, secs: u32) -> Result<Vec<PmdRxq>> {
+        let params = pmd_rxq_window_params(secs)?;
+        let params: Vec<&str> = params.iter().map(String::as_str).collect();
+        parse::dpif_netdev::pmd_rxq_show(
+            &self.run_output("dpif-netdev/pmd-rxq-show", Some(&params))?,
+        )
+    }
+
+    /// Suggests a better balanced assignment of receive queues to PMD threads, returned as
+    /// `(port, queue, core)` tuples.
+    ///
+    /// The suggestion is computed from the current queue usages (see
+    /// [`OvsUnixCtl::pmd_rxq_show`]) by greedily assigning the busiest queues first to the least
+    /// loaded core. Isolated cores and the queues they poll are left untouched. Nothing is
+    /// changed on the daemon.
+    pub fn suggest_rxq_balance(&mut self) -> Result<Vec<(String, u32, u32)>> {
+        Ok(balance_rxqs(&self.pmd_rxq_show()?))
+    }
+
+    /// Runs the "dpif-netdev/pmd-stats-show" command and returns the statistics of each PMD
+    /// thread, and of the main thread which handles the ports not polled by PMD threads.
+    pub fn pmd_stats_show(&mut self) -> Result<Vec<PmdStats>> {
+        parse::dpif_netdev::pmd_stats_show(&self.run_output("dpif-netdev/pmd-stats-show", None)?)
+    }
+
+    /// Runs the "dpif-netdev/subtable-lookup-info-get" command and returns the available subtable
+    /// lookup implementations, sorted by priority (highest first).
+    pub fn subtable_lookup_info(&mut self) -> Result<Vec<SubtableInfo>> {
+        parse::dpif_netdev::subtable_lookup_info(
+            &self.run_output("dpif-netdev/subtable-lookup-info-get", None)?,
+        )
+    }
+}
+
+/// Builds the parameters of "dpif-netdev/pmd-rxq-show" to use a window of `secs` seconds.
+fn pmd_rxq_window_params(secs: u32) -> Result<Vec<String>> {
+    if !PMD_RXQ_WINDOW_SECS.contains(&secs) {
+        return Err(Error::InvalidArgument(format!(
+            "pmd-rxq-show window must be between {} and {} seconds: {secs}",
+            PMD_RXQ_WINDOW_SECS.start(),
+            PMD_RXQ_WINDOW_SECS.end(),
+        )));
+    }
+    Ok(vec!["-secs".to_string(), secs.to_string()])
+}
+
+/// Greedily assigns the queues polled by non-isolated PMD threads, busiest first, to the least
+/// loaded non-isolated core.
+fn balance_rxqs(pmds: &[PmdRxq]) -> Vec<(String, u32, u32)> {
+    let mut cores: Vec<(u32, u64)> = pmds
+        .iter()
+        .filter(|pmd| !pmd.isolated)
+        .map(|pmd| (pmd.core_id, 0))
+        .collect();
+    let mut rxqs: Vec<&RxqUsage> = pmds
+        .iter()
+        .filter(|pmd| !pmd.isolated)
+        .flat_map(|pmd| pmd.rxqs.iter())
+        .collect();
+    rxqs.sort_by_key(|rxq| Reverse(rxq.usage.unwrap_or_default()));
+
+    let mut suggestion = Vec::new();
+    for rxq in rxqs {
+        let Some(core) = cores.iter_mut().min_by_key(|(id, load)| (*load, *id)) else {
+            break;
+        };
+        core.1 += rxq.usage.unwrap_or_default() as u64;
+        suggestion.push((rxq.port.clone(), rxq.queue, core.0));
+    }
+    suggestion
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rxq(port: &str, queue: u32, usage: u32) -> RxqUsage {
+        RxqUsage {
+            port: port.to_string(),
+            queue,
+            enabled: true,
+            usage: Some(usage),
+            cross_numa: None,
+        }
+    }
+
+    #[test]
+    fn rxq_window_params() {
+        assert_eq!(
+            pmd_rxq_window_params(30).unwrap(),
+            vec!["-secs".to_string(), "30".to_string()]
+        );
+        assert!(pmd_rxq_window_params(1).is_ok());
+        assert!(pmd_rxq_window_params(60).is_ok());
+        assert!(matches!(
+            pmd_rxq_window_params(0),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            pmd_rxq_window_params(61),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn pmd_stats_ratios() {
+        let stats = PmdStats {
+            packets_received: 80,
+            recirculations: 20,
+            emc_hits: 50,
+            smc_hits: 10,
+            megaflow_hits: 30,
+            misses: 8,
+            lost: 2,
+            idle_cycles: 300,
+            busy_cycles: 100,
+            ..Default::default()
+        };
+        assert_eq!(stats.passes(), 100);
+        assert_eq!(stats.emc_hit_ratio(), Some(0.5));
+        assert_eq!(stats.smc_hit_ratio(), Some(0.1));
+        assert_eq!(stats.megaflow_hit_ratio(), Some(0.3));
+        assert_eq!(stats.miss_ratio(), Some(0.1));
+        assert_eq!(stats.busy_ratio(), Some(0.25));
+
+        let idle = PmdStats::default();
+        assert_eq!(idle.emc_hit_ratio(), None);
+        assert_eq!(idle.busy_ratio(), None);
+    }
+
+    #[test]
+    fn cross_numa() {
+        let mut pmd = PmdRxq {
+            numa_id: 1,
+            core_id: 3,
+            isolated: false,
+            rxqs: vec![rxq("dpdk0", 0, 40), rxq("dpdk1", 0, 20), rxq("vhu0", 0, 5)],
+        };
+        pmd.set_port_numa(&HashMap::from([
+            ("dpdk0".to_string(), 0),
+            ("dpdk1".to_string(), 1),
+        ]));
+        assert_eq!(
+            pmd.rxqs
+                .iter()
+                .map(|rxq| rxq.cross_numa)
+                .collect::<Vec<_>>(),
+            vec![Some(true), Some(false), None]
+        );
+    }
+
+    #[test]
+    fn rxq_balance() {
+        let pmds = vec![
+            PmdRxq {
+                numa_id: 0,
+                core_id: 2,
+                isolated: false,
+                rxqs: vec![
+                    rxq("dpdk0", 0, 40),
+                    rxq("dpdk0", 1, 30),
+                    rxq("dpdk1", 0, 20),
+                ],
+            },
+            PmdRxq {
+                numa_id: 0,
+                core_id: 4,
+                isolated: false,
+                rxqs: vec![rxq("dpdk1", 1, 10)],
+            },
+            PmdRxq {
+                numa_id: 0,
+                core_id: 6,
+                isolated: true,
+                rxqs: vec![rxq("vhu0", 0, 5)],
+            },
+        ];
+        let max_load = |assignment: &[(u32, u32)]| {
+            let mut loads: HashMap<u32, u32> = HashMap::new();
+            assignment
+                .iter()
+                .for_each(|(core, usage)| *loads.entry(*core).or_default() += usage);
+            loads.into_values().max().unwrap()
+        };
+        let usage = |port: &str, queue: u32| {
+            pmds.iter()
+                .flat_map(|pmd| pmd.rxqs.iter())
+                .find(|rxq| rxq.port == port && rxq.queue == queue)
+                .unwrap()
+                .usage
+                .unwrap()
+        };
+
+        let suggestion = balance_rxqs(&pmds);
+        assert_eq!(
+            suggestion,
+            vec![
+                ("dpdk0".to_string(), 0, 2),
+                ("dpdk0".to_string(), 1, 4),
+                ("dpdk1".to_string(), 0, 4),
+                ("dpdk1".to_string(), 1, 2),
+            ]
+        );
+
+        let current: Vec<(u32, u32)> = pmds
+            .iter()
+            .filter(|pmd| !pmd.isolated)
+            .flat_map(|pmd| pmd.rxqs.iter().map(|rxq| (pmd.core_id, rxq.usage.unwrap())))
+            .collect();
+        let suggested: Vec<(u32, u32)> = suggestion
+            .iter()
+            .map(|(port, queue, core)| (*core, usage(port, *queue)))
+            .collect();
+        assert_eq!(max_load(&current), 90);
+        assert_eq!(max_load(&suggested), 50);
+    }
+}
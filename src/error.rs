@@ -16,9 +16,18 @@ pub enum Error {
     /// An error occurred in the socket I/O handling
     #[error("input/output socket error: {0}")]
     Socket(#[from] io::Error),
+    /// An error occurred in the socket I/O handling while talking to a known peer
+    #[error("input/output error on {peer}: {source}")]
+    SocketIo { peer: String, source: io::Error },
     /// The connection timed-out waiting for a response
     #[error("connection timeout")]
     Timeout,
+    /// The response was cut in the middle, e.g: the read timeout fired while receiving it
+    #[error("response truncated after {bytes_read} bytes, the timeout might be too short")]
+    TruncatedResponse { bytes_read: u64 },
+    /// The daemon closed the connection before replying to a command
+    #[error("daemon closed the connection while running {cmd}")]
+    DaemonClosed { cmd: String },
     /// The remote peer returned an error
     #[error("command {cmd}({params}) returns error: {error}")]
     Command {
@@ -26,9 +35,24 @@ pub enum Error {
         params: String,
         error: String,
     },
+    /// The daemon doesn't know the command, e.g: it is too old to support it
+    #[error("unknown command {cmd}")]
+    UnknownCommand { cmd: String },
+    /// The command name is invalid, e.g: a full command line was given instead
+    #[error("invalid command name {cmd:?}: command names contain no whitespace, use run_str to run a full command line")]
+    InvalidCommand { cmd: String },
+    /// The command was not sent because the client is in dry-run mode
+    #[error("dry-run: would run {cmd}({params})")]
+    DryRun { cmd: String, params: String },
+    /// An invalid argument was provided, the command was not sent
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
     /// An error occurred when trying to find the right unix socket
     #[error("socket not found: {0}")]
     SocketNotFound(String),
+    /// The target daemon was not found and its name looks like a typo of a known daemon
+    #[error("{target} is not running, did you mean {suggestion}?")]
+    UnknownTarget { target: String, suggestion: String },
     /// OpenvSwitch is not running
     #[error("OpenvSwitch is not running")]
     OvsNotRunning,
@@ -41,6 +65,147 @@ pub enum Error {
     },
 }
 
+impl Error {
+    /// Returns a short code identifying the kind of error, e.g: for structured logging or
+    /// metrics aggregation.
+    ///
+    /// Codes are stable across versions: existing codes are never changed, new kinds of errors
+    /// get new codes. The codes are: `protocol`, `serialize`, `socket`, `timeout`,
+    /// `truncated_response`, `daemon_closed`, `command`, `unknown_command`, `invalid_command`,
+    /// `dry_run`, `invalid_argument`, `socket_not_found`, `unknown_target`, `not_running` and
+    /// `invalid_response`. Socket errors share the `socket` code whether the peer is known or not.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Protocol(_) => "protocol",
+            Error::Serialize(_) => "serialize",
+            Error::Socket(_) | Error::SocketIo { .. } => "socket",
+            Error::Timeout => "timeout",
+            Error::TruncatedResponse { .. } => "truncated_response",
+            Error::DaemonClosed { .. } => "daemon_closed",
+            Error::Command { .. } => "command",
+            Error::UnknownCommand { .. } => "unknown_command",
+            Error::InvalidCommand { .. } => "invalid_command",
+            Error::DryRun { .. } => "dry_run",
+            Error::InvalidArgument(_) => "invalid_argument",
+            Error::SocketNotFound(_) => "socket_not_found",
+            Error::UnknownTarget { .. } => "unknown_target",
+            Error::OvsNotRunning => "not_running",
+            Error::OvsInvalidResponse { .. } => "invalid_response",
+        }
+    }
+
+    /// Returns whether the same request might succeed if sent again, e.g: after a short delay.
+    ///
+    /// Errors reported by the daemon itself, such as failed commands or unparsable responses, are
+    /// never retryable.
+    pub fn is_retryable(&self) -> bool {
+        use io::ErrorKind::*;
+        match self {
+            Error::Timeout | Error::OvsNotRunning => true,
+            _ => self
+                .io_error()
+                .is_some_and(|e| matches!(e.kind(), WouldBlock | Interrupted | ConnectionReset)),
+        }
+    }
+
+    /// Returns whether the error means the connection is broken and a request failing with it can
+    /// be sent again on a new connection. Timeouts and protocol errors are not such errors: the
+    /// daemon might have received (and run) the request.
+    pub fn is_connection_error(&self) -> bool {
+        use io::ErrorKind::*;
+        self.io_error().is_some_and(|e| {
+            matches!(
+                e.kind(),
+                BrokenPipe | ConnectionReset | ConnectionAborted | NotConnected
+            )
+        })
+    }
+
+    /// Returns the underlying I/O error of socket errors.
+    pub(crate) fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Error::Socket(e) | Error::SocketIo { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Errors are compared by value, which is mostly useful in tests. I/O and (de)serialization errors
+/// can't be compared as such: they are equal if their kind, respectively their category, is.
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        use Error::*;
+        match (self, other) {
+            (Protocol(a), Protocol(b)) => a == b,
+            (Serialize(a), Serialize(b)) => a.classify() == b.classify(),
+            (Socket(a), Socket(b)) => a.kind() == b.kind(),
+            (
+                SocketIo {
+                    peer: a_peer,
+                    source: a_source,
+                },
+                SocketIo {
+                    peer: b_peer,
+                    source: b_source,
+                },
+            ) => a_peer == b_peer && a_source.kind() == b_source.kind(),
+            (Timeout, Timeout) => true,
+            (TruncatedResponse { bytes_read: a }, TruncatedResponse { bytes_read: b }) => a == b,
+            (DaemonClosed { cmd: a }, DaemonClosed { cmd: b }) => a == b,
+            (
+                Command {
+                    cmd: a_cmd,
+                    params: a_params,
+                    error: a_error,
+                },
+                Command {
+                    cmd: b_cmd,
+                    params: b_params,
+                    error: b_error,
+                },
+            ) => (a_cmd, a_params, a_error) == (b_cmd, b_params, b_error),
+            (UnknownCommand { cmd: a }, UnknownCommand { cmd: b }) => a == b,
+            (InvalidCommand { cmd: a }, InvalidCommand { cmd: b }) => a == b,
+            (
+                DryRun {
+                    cmd: a_cmd,
+                    params: a_params,
+                },
+                DryRun {
+                    cmd: b_cmd,
+                    params: b_params,
+                },
+            ) => (a_cmd, a_params) == (b_cmd, b_params),
+            (InvalidArgument(a), InvalidArgument(b)) => a == b,
+            (SocketNotFound(a), SocketNotFound(b)) => a == b,
+            (
+                UnknownTarget {
+                    target: a_target,
+                    suggestion: a_suggestion,
+                },
+                UnknownTarget {
+                    target: b_target,
+                    suggestion: b_suggestion,
+                },
+            ) => (a_target, a_suggestion) == (b_target, b_suggestion),
+            (OvsNotRunning, OvsNotRunning) => true,
+            (
+                OvsInvalidResponse {
+                    cmd: a_cmd,
+                    response: a_response,
+                    error: a_error,
+                },
+                OvsInvalidResponse {
+                    cmd: b_cmd,
+                    response: b_response,
+                    error: b_error,
+                },
+            ) => (a_cmd, a_response, a_error) == (b_cmd, b_response, b_error),
+            _ => false,
+        }
+    }
+}
+
 impl From<serde_json::Error> for Error {
     fn from(error: serde_json::Error) -> Error {
         // serde_json errors can encapsulate IO errors.
@@ -51,3 +216,142 @@ impl From<serde_json::Error> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes() {
+        let s = String::default;
+        for (error, code) in [
+            (Error::Protocol(s()), "protocol"),
+            (
+                Error::Serialize(serde_json::from_str::<u32>("x").unwrap_err()),
+                "serialize",
+            ),
+            (Error::Socket(io::ErrorKind::Other.into()), "socket"),
+            (
+                Error::SocketIo {
+                    peer: s(),
+                    source: io::ErrorKind::Other.into(),
+                },
+                "socket",
+            ),
+            (Error::Timeout, "timeout"),
+            (
+                Error::TruncatedResponse { bytes_read: 0 },
+                "truncated_response",
+            ),
+            (Error::DaemonClosed { cmd: s() }, "daemon_closed"),
+            (
+                Error::Command {
+                    cmd: s(),
+                    params: s(),
+                    error: s(),
+                },
+                "command",
+            ),
+            (Error::UnknownCommand { cmd: s() }, "unknown_command"),
+            (Error::InvalidCommand { cmd: s() }, "invalid_command"),
+            (
+                Error::DryRun {
+                    cmd: s(),
+                    params: s(),
+                },
+                "dry_run",
+            ),
+            (Error::InvalidArgument(s()), "invalid_argument"),
+            (Error::SocketNotFound(s()), "socket_not_found"),
+            (
+                Error::UnknownTarget {
+                    target: s(),
+                    suggestion: s(),
+                },
+                "unknown_target",
+            ),
+            (Error::OvsNotRunning, "not_running"),
+            (
+                Error::OvsInvalidResponse {
+                    cmd: s(),
+                    response: s(),
+                    error: s(),
+                },
+                "invalid_response",
+            ),
+        ] {
+            assert_eq!(error.code(), code);
+        }
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(Error::Timeout, Error::Timeout);
+        assert_ne!(Error::Timeout, Error::OvsNotRunning);
+        assert_eq!(
+            Error::InvalidArgument("x".to_string()),
+            Error::InvalidArgument("x".to_string())
+        );
+        assert_ne!(
+            Error::InvalidArgument("x".to_string()),
+            Error::SocketNotFound("x".to_string())
+        );
+        assert_eq!(
+            Error::Socket(io::Error::new(io::ErrorKind::BrokenPipe, "first")),
+            Error::Socket(io::Error::new(io::ErrorKind::BrokenPipe, "second"))
+        );
+        assert_ne!(
+            Error::Socket(io::ErrorKind::BrokenPipe.into()),
+            Error::Socket(io::ErrorKind::ConnectionReset.into())
+        );
+        assert_eq!(
+            Error::from(serde_json::from_str::<u32>("x").unwrap_err()),
+            Error::from(serde_json::from_str::<u32>("y").unwrap_err())
+        );
+        assert_ne!(
+            Error::from(serde_json::from_str::<u32>("x").unwrap_err()),
+            Error::from(serde_json::from_str::<u32>("\"x\"").unwrap_err())
+        );
+    }
+
+    #[test]
+    fn retryable() {
+        let socket = |kind: io::ErrorKind| Error::Socket(kind.into());
+
+        for error in [
+            Error::Timeout,
+            Error::OvsNotRunning,
+            socket(io::ErrorKind::WouldBlock),
+            socket(io::ErrorKind::Interrupted),
+            socket(io::ErrorKind::ConnectionReset),
+        ] {
+            assert!(error.is_retryable(), "{error}");
+        }
+        for error in [
+            Error::Serialize(serde_json::from_str::<u32>("x").unwrap_err()),
+            Error::Command {
+                cmd: "foo".to_string(),
+                params: String::new(),
+                error: "failed".to_string(),
+            },
+            Error::OvsInvalidResponse {
+                cmd: "foo".to_string(),
+                response: String::new(),
+                error: "invalid".to_string(),
+            },
+            socket(io::ErrorKind::PermissionDenied),
+        ] {
+            assert!(!error.is_retryable(), "{error}");
+        }
+
+        assert!(socket(io::ErrorKind::BrokenPipe).is_connection_error());
+        assert!(socket(io::ErrorKind::ConnectionReset).is_connection_error());
+        assert!(!socket(io::ErrorKind::WouldBlock).is_connection_error());
+        assert!(!Error::Timeout.is_connection_error());
+        assert!(Error::SocketIo {
+            peer: "unix:///run/openvswitch/ovs-vswitchd.1.ctl".to_string(),
+            source: io::ErrorKind::BrokenPipe.into(),
+        }
+        .is_connection_error());
+    }
+}
@@ -0,0 +1,119 @@
+//! MAC learning table (fdb) helpers.
+
+use std::{fmt, str::FromStr};
+
+use crate::{error::Error, parse, OvsUnixCtl, Result};
+
+/// An Ethernet MAC address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl FromStr for MacAddr {
+    type Err = Error;
+
+    /// Parses a MAC address made of six colon-separated hexadecimal octets, e.g:
+    /// `52:54:00:12:34:56`.
+    fn from_str(s: &str) -> Result<MacAddr> {
+        let invalid = parse::InvalidResponse::new("", s);
+
+        let mut mac = [0; 6];
+        let mut octets = s.split(':');
+        for byte in mac.iter_mut() {
+            *byte = octets
+                .next()
+                .filter(|o| o.len() == 2 && o.chars().all(|c| c.is_ascii_hexdigit()))
+                .and_then(|o| u8::from_str_radix(o, 16).ok())
+                .ok_or_else(|| invalid.error(format!("invalid MAC address {s}")))?;
+        }
+        if octets.next().is_some() {
+            return Err(invalid.error(format!("invalid MAC address {s}")));
+        }
+        Ok(MacAddr(mac))
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+/// MAC addresses are serialized in their usual string form.
+#[cfg(feature = "serde")]
+impl serde::Serialize for MacAddr {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// An entry of a bridge MAC learning table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FdbEntry {
+    /// OpenFlow port the MAC address was learned on. The local port is reported as `65534`
+    /// (OFPP_LOCAL).
+    pub port: u32,
+    /// VLAN the MAC address was learned on.
+    pub vlan: u16,
+    /// The learned MAC address.
+    pub mac: MacAddr,
+    /// Age of the entry in seconds. Static entries, which never age, are reported as
+    /// `u32::MAX`.
+    pub age: u32,
+}
+
+impl OvsUnixCtl {
+    /// Runs the "fdb/show" command and returns the MAC learning table of the given bridge.
+    pub fn fdb_show(&mut self, bridge: &str) -> Result<Vec<FdbEntry>> {
+        parse::fdb::show(&self.run_output("fdb/show", Some(&[bridge]))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mac_addr() {
+        let mac: MacAddr = "52:54:00:AB:cd:0f".parse().unwrap();
+        assert_eq!(mac, MacAddr([0x52, 0x54, 0x00, 0xab, 0xcd, 0x0f]));
+        assert_eq!(mac.to_string(), "52:54:00:ab:cd:0f");
+
+        for invalid in [
+            "",
+            "52:54:00:ab:cd",
+            "52:54:00:ab:cd:0f:00",
+            "52:54:00:ab:cd:0g",
+            "52:54:00:ab:cd:f",
+            "52-54-00-ab-cd-0f",
+            "52:54:00:ab:cd:+f",
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<MacAddr>(),
+                    Err(Error::OvsInvalidResponse { .. })
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serialize() {
+        let entry = FdbEntry {
+            port: 1,
+            vlan: 0,
+            mac: MacAddr([0x52, 0x54, 0x00, 0xab, 0xcd, 0x0f]),
+            age: 12,
+        };
+        assert_eq!(
+            serde_json::to_value(entry).unwrap(),
+            serde_json::json!({"port": 1, "vlan": 0, "mac": "52:54:00:ab:cd:0f", "age": 12})
+        );
+    }
+}
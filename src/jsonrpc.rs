@@ -1,14 +1,26 @@
 //! A simple JSON-RPC client compatible with OVS unixctl.
 
 use std::{
-    fmt, path,
-    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+    fmt, io, net,
+    os::unix::io::RawFd,
+    path,
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        Arc,
+    },
     time,
 };
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{value::RawValue, Deserializer};
 
-use crate::{error::*, unix, Result};
+use crate::{
+    error::*,
+    metrics::{Metrics, NoopMetrics},
+    tcp,
+    transport::{CustomTransport, Transport},
+    unix, Result,
+};
 
 // JsonStreams are capable of sending and receiving JSON messages.
 pub(crate) trait JsonStream {
@@ -19,6 +31,23 @@ pub(crate) trait JsonStream {
     fn recv<R>(&mut self) -> Result<R>
     where
         R: for<'a> Deserialize<'a>;
+
+    /// Get the number of bytes sent and received on the stream so far.
+    fn bytes(&self) -> (u64, u64);
+
+    /// Get the read and write timeout.
+    fn timeout(&self) -> Result<Option<time::Duration>>;
+
+    /// Set the read and write timeout.
+    fn set_timeout(&mut self, timeout: Option<time::Duration>) -> Result<()>;
+
+    /// Receive a message from the target if one was fully received already, without blocking.
+    fn try_recv<R>(&mut self) -> Result<Option<R>>
+    where
+        R: for<'a> Deserialize<'a>;
+
+    /// Get the file descriptor of the underlying socket, if any.
+    fn raw_fd(&self) -> Option<RawFd>;
 }
 
 // Client streams can connect and disconnect from targets creating
@@ -31,94 +60,380 @@ pub(crate) trait JsonStreamClient: fmt::Display {
 
 /// A JSON-RPC request.
 #[derive(Debug, Serialize)]
-pub struct Request<'a, P: Serialize + AsRef<str> = &'a str> {
+pub struct Request<'a, P: Serialize = &'a str> {
     /// The name of the RPC call.
     pub method: &'a str,
     /// Parameters to the RPC call.
     pub params: &'a [P],
     /// Identifier for this request, which should appear in the response.
-    pub id: usize,
+    pub id: RpcId,
 }
 
-/// A JSONRPC response object.
-/// TODO make generic
-#[derive(Debug, Clone, Deserialize)]
-pub(crate) struct Response<R = String> {
-    /// The result of the request.
+/// A JSON-RPC notification, i.e: a request the peer doesn't reply to.
+#[derive(Debug, Serialize)]
+struct Notification<'a, P: Serialize> {
+    method: &'a str,
+    params: &'a [P],
+    /// Notifications are identified by a null id, which is what the unit type serializes to.
+    id: (),
+}
+
+/// A JSON-RPC response.
+///
+/// Unixctl commands return their output as a string result, hence the default result type. Other
+/// peers might return any JSON value.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Response<R = String> {
+    /// The result of the request, `None` if the request failed (or returned nothing).
     pub result: Option<R>,
-    /// An error if it occurred.
+    /// The error message returned by the peer if the request failed. Errors which are not
+    /// strings, e.g: the objects OVSDB servers return, are converted to one.
+    #[serde(default, deserialize_with = "error_string")]
     pub error: Option<String>,
-    /// Identifier for this response. It should match that of the associated request.
-    pub id: Option<usize>,
+    /// Identifier for this response. It matches the one of the associated request, or is
+    /// [`RpcId::Null`] for notifications.
+    #[serde(default)]
+    pub id: RpcId,
+}
+
+impl Response<Box<RawValue>> {
+    /// Decodes the result. Responses are received undecoded for stale ones, which might carry a
+    /// result of another type, to be skipped first.
+    fn decode<R: DeserializeOwned>(self) -> Result<Response<R>> {
+        Ok(Response {
+            result: self
+                .result
+                .map(|result| serde_json::from_str(result.get()))
+                .transpose()?,
+            error: self.error,
+            id: self.id,
+        })
+    }
+}
+
+/// A JSON-RPC message identifier.
+///
+/// Requests built by this crate use increasing numbers, but peers might use any shape.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RpcId {
+    /// A numeric id.
+    Num(u64),
+    /// A string id.
+    Str(String),
+    /// A null (or missing) id, as used by notifications.
+    #[default]
+    Null,
+}
+
+impl fmt::Display for RpcId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcId::Num(id) => write!(f, "{id}"),
+            RpcId::Str(id) => write!(f, "{id:?}"),
+            RpcId::Null => f.write_str("null"),
+        }
+    }
+}
+
+/// Deserializes the error of a response into a string. OVSDB servers return objects with an
+/// "error" and an optional "details" members, which are joined; any other value is kept as JSON.
+fn error_string<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        Option::<serde_json::Value>::deserialize(deserializer)?.map(|error| match error {
+            serde_json::Value::String(error) => error,
+            serde_json::Value::Object(ref obj) => match (obj.get("error"), obj.get("details")) {
+                (Some(serde_json::Value::String(e)), Some(serde_json::Value::String(d))) => {
+                    format!("{e}: {d}")
+                }
+                (Some(serde_json::Value::String(e)), None) => e.clone(),
+                _ => error.to_string(),
+            },
+            _ => error.to_string(),
+        }),
+    )
+}
+
+/// Function called after each successful reconnection with the transport used before and after
+/// it, and the error that triggered it if any.
+type ReconnectFn<C> = dyn FnMut(&C, &C, Option<&Error>) + Send;
+
+/// Wrapper of the reconnection function, for it to be stored in a [`Client`].
+pub(crate) struct ReconnectHook<C>(pub(crate) Box<ReconnectFn<C>>);
+
+impl<C> fmt::Debug for ReconnectHook<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ReconnectHook")
+    }
+}
+
+/// Function building the transport to use for a reconnection out of the current one, e.g: to
+/// look the socket up again.
+type ResolveFn<C> = dyn FnMut(&C) -> Result<C> + Send;
+
+/// Wrapper of the resolution function, for it to be stored in a [`Client`].
+pub(crate) struct Resolver<C>(pub(crate) Box<ResolveFn<C>>);
+
+impl<C> fmt::Debug for Resolver<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Resolver")
+    }
 }
 
 /// JSON-RPC client.
 #[derive(Debug)]
 pub(crate) struct Client<C: JsonStreamClient> {
+    stream_client: C,
     stream: C::Stream,
     last_id: AtomicUsize,
+    /// Time after which an unused connection is closed and a new one is created on the next call.
+    idle_timeout: Option<time::Duration>,
+    last_activity: time::Instant,
+    on_reconnect: Option<ReconnectHook<C>>,
+    /// Resolves the transport to use on reconnections. The current one is reused if unset.
+    resolver: Option<Resolver<C>>,
+    /// Whether requests failing because the connection was broken are retried once on a new one.
+    auto_reconnect: bool,
+    metrics: Arc<dyn Metrics>,
 }
 
 impl<C: JsonStreamClient> Client<C> {
     /// Creates a new client with the given transport.
     pub(crate) fn new(mut stream_client: C) -> Result<Client<C>> {
-        let stream = stream_client.connect()?;
+        let stream = stream_client
+            .connect()
+            .map_err(|e| peer_error(&stream_client, e))?;
         Ok(Client {
+            stream_client,
             stream,
             last_id: AtomicUsize::new(1),
+            idle_timeout: None,
+            last_activity: time::Instant::now(),
+            on_reconnect: None,
+            resolver: None,
+            auto_reconnect: false,
+            metrics: Arc::new(NoopMetrics),
         })
     }
 
-    /// Creates a new client with a Unix socket transport.
-    pub(crate) fn unix<P: AsRef<path::Path>>(
-        sock_path: P,
-        timeout: Option<time::Duration>,
-    ) -> Result<Client<unix::UnixJsonStreamClient>> {
-        let mut stream_client = unix::UnixJsonStreamClient::new(sock_path);
-        if let Some(timeout) = timeout {
-            stream_client = stream_client.timeout(timeout);
+    /// Returns the transport currently in use.
+    pub(crate) fn stream_client(&self) -> &C {
+        &self.stream_client
+    }
+
+    /// Sets the idle timeout.
+    pub(crate) fn idle_timeout(mut self, timeout: time::Duration) -> Client<C> {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the receiver of the calls telemetry.
+    pub(crate) fn metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.metrics = metrics;
+    }
+
+    /// Sets the function called after each successful reconnection.
+    pub(crate) fn on_reconnect(&mut self, hook: ReconnectHook<C>) {
+        self.on_reconnect = Some(hook);
+    }
+
+    /// Sets the function resolving the transport to use on reconnections.
+    fn resolver(&mut self, resolver: Resolver<C>) {
+        self.resolver = Some(resolver);
+    }
+
+    /// Enables or disables retrying requests failing because the connection was broken.
+    pub(crate) fn auto_reconnect(&mut self, enabled: bool) {
+        self.auto_reconnect = enabled;
+    }
+
+    /// Closes the current connection and creates a new one. The error that triggered the
+    /// reconnection, if any, is reported to the reconnection hook.
+    pub(crate) fn reconnect(&mut self, error: Option<&Error>) -> Result<()> {
+        let mut old = None;
+        if let Some(resolver) = &mut self.resolver {
+            let stream_client = (resolver.0)(&self.stream_client)?;
+            old = Some(std::mem::replace(&mut self.stream_client, stream_client));
+        }
+        self.stream = self
+            .stream_client
+            .connect()
+            .map_err(|e| peer_error(&self.stream_client, e))?;
+        if let Some(hook) = &mut self.on_reconnect {
+            (hook.0)(
+                old.as_ref().unwrap_or(&self.stream_client),
+                &self.stream_client,
+                error,
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs the provided function with a different read and write timeout. The previous timeout
+    /// is restored afterwards, even if the function fails.
+    pub(crate) fn with_timeout<T, F>(&mut self, timeout: time::Duration, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        // Make sure a reconnection due to the idle timeout won't happen in the middle.
+        let stream = self.active_stream()?;
+        let prev = stream
+            .timeout()
+            .and_then(|prev| stream.set_timeout(Some(timeout)).map(|_| prev))
+            .map_err(|e| peer_error(&self.stream_client, e))?;
+
+        let res = f(self);
+        let restored = self.stream.set_timeout(prev);
+        let res = res?;
+        restored.map_err(|e| peer_error(&self.stream_client, e))?;
+        Ok(res)
+    }
+
+    /// Returns the stream to use for the next call, reconnecting first if the connection has been
+    /// idle for longer than the idle timeout.
+    fn active_stream(&mut self) -> Result<&mut C::Stream> {
+        if self
+            .idle_timeout
+            .is_some_and(|timeout| self.last_activity.elapsed() > timeout)
+        {
+            self.reconnect(None)?;
         }
-        Client::new(stream_client)
+        self.last_activity = time::Instant::now();
+        Ok(&mut self.stream)
+    }
+
+    /// Reports a call to the metrics. `bytes` are the stream byte counters before the call.
+    fn record(&self, method: &str, start: time::Instant, bytes: (u64, u64), success: bool) {
+        let (sent, received) = self.stream.bytes();
+        self.metrics.record_call(method, start.elapsed(), success);
+        self.metrics.record_bytes(
+            sent.saturating_sub(bytes.0),
+            received.saturating_sub(bytes.1),
+        );
     }
 
     /// Builds a request with the given method and parameters.
     ///
     /// It internally deals with incrementing the id.
-    fn build_request<'a, P: Serialize + AsRef<str>>(
-        &self,
-        method: &'a str,
-        params: &'a [P],
-    ) -> Request<'a, P> {
+    fn build_request<'a, P: Serialize>(&self, method: &'a str, params: &'a [P]) -> Request<'a, P> {
         Request {
             method,
             params,
-            id: self.last_id.fetch_add(1, Relaxed),
+            id: RpcId::Num(self.last_id.fetch_add(1, Relaxed) as u64),
         }
     }
 
+    /// Sends a notification, i.e: a request the peer doesn't reply to, and returns as soon as it
+    /// is sent. As a consequence, failures happening on the peer side are not reported.
+    pub(crate) fn notify<P: Serialize>(&mut self, method: &str, params: &[P]) -> Result<()> {
+        let start = time::Instant::now();
+        let stream = self.active_stream()?;
+        let bytes = stream.bytes();
+
+        let res = stream.send(Notification {
+            method,
+            params,
+            id: (),
+        });
+        self.record(method, start, bytes, res.is_ok());
+        res.map_err(|e| peer_error(&self.stream_client, e))
+    }
+
+    /// Sends a request without waiting for its response and returns its id. The response can be
+    /// received later, e.g: using [`Client::try_recv_pending`].
+    pub(crate) fn send<P: Serialize>(&mut self, method: &str, params: &[P]) -> Result<RpcId> {
+        let request = self.build_request(method, params);
+        let id = request.id.clone();
+        self.active_stream()?
+            .send(request)
+            .map_err(|e| peer_error(&self.stream_client, e))?;
+        Ok(id)
+    }
+
+    /// Receives a message if one was fully received already, without blocking.
+    pub(crate) fn try_recv_pending<R: DeserializeOwned>(&mut self) -> Result<Option<Response<R>>> {
+        let res = self
+            .stream
+            .try_recv()
+            .map_err(|e| peer_error(&self.stream_client, e))?;
+        if res.is_some() {
+            self.last_activity = time::Instant::now();
+        }
+        Ok(res)
+    }
+
+    /// Returns the file descriptor of the current connection, if the transport has one.
+    pub(crate) fn raw_fd(&self) -> Option<RawFd> {
+        self.stream.raw_fd()
+    }
+
     /// Sends a request and returns the response.
-    pub fn send_request<R: DeserializeOwned, P: Serialize + AsRef<str>>(
+    ///
+    /// Responses to previous requests received in the meantime, e.g: late responses to requests
+    /// which timed out, are discarded until the response to this request is received or the
+    /// timeout fires.
+    ///
+    /// If automatic reconnection is enabled and the request fails because the connection was
+    /// broken, a new connection is made and the request is sent again, once.
+    pub fn send_request<R: DeserializeOwned, P: Serialize>(
         &mut self,
         request: Request<P>,
     ) -> Result<Response<R>> {
-        let stream = &mut self.stream;
-        let req_id = request.id;
-
-        stream.send(request)?;
-        let res: Response<R> = stream.recv()?;
-        if res
-            .id
-            .ok_or_else(|| Error::Protocol("id not found in response".to_string()))?
-            != req_id
-        {
-            return Err(Error::Protocol(
-                "request and response ids do not match".to_string(),
-            ));
+        match self.send_request_once(&request) {
+            Err(e) if self.auto_reconnect && e.is_connection_error() => {
+                self.reconnect(Some(&e))?;
+                self.send_request_once(&request)
+            }
+            res => res,
         }
+    }
+
+    /// Sends a request on the current connection and returns the response.
+    fn send_request_once<R: DeserializeOwned, P: Serialize>(
+        &mut self,
+        request: &Request<P>,
+    ) -> Result<Response<R>> {
+        let start = time::Instant::now();
+        let stream = self.active_stream()?;
+        let bytes = stream.bytes();
+        let req_id = request.id.clone();
+        let method = request.method;
+
+        let res: Result<Response<R>> = stream.send(request).and_then(|_| loop {
+            let res: Response<Box<RawValue>> =
+                stream.recv().map_err(|e| closed_error(e, method))?;
+            if is_stale(&res.id, &req_id) {
+                continue;
+            }
+            break res.decode();
+        });
+        self.record(
+            method,
+            start,
+            bytes,
+            res.as_ref().is_ok_and(|r| r.error.is_none()),
+        );
+        let res = res.map_err(|e| peer_error(&self.stream_client, e))?;
+        self.last_activity = time::Instant::now();
+        check_id(&res.id, &req_id)?;
 
         Ok(res)
     }
 
+    /// Calls a method with some arguments and returns the response as is, without converting the
+    /// error it might carry.
+    pub(crate) fn call_raw<R: DeserializeOwned, P: Serialize>(
+        &mut self,
+        method: &str,
+        params: &[P],
+    ) -> Result<Response<R>> {
+        let request = self.build_request(method, params);
+        self.send_request(request)
+    }
+
     /// Calls a method with some arguments and returns the result.
     pub(crate) fn call_params<R: DeserializeOwned, P: Serialize + AsRef<str>>(
         &mut self,
@@ -128,30 +443,741 @@ impl<C: JsonStreamClient> Client<C> {
         let request = self.build_request(method, params);
         let response = self.send_request(request)?;
         if let Some(error) = response.error {
-            return Err(Error::Command {
-                cmd: String::from(method),
-                params: params
-                    .iter()
-                    .map(|p| p.as_ref())
-                    .collect::<Vec<&str>>()
-                    .join(", "),
-                error,
-            });
+            return Err(command_error(method, params, error));
         }
         Ok(response)
     }
 
+    /// Calls a method with arbitrary JSON arguments and returns the result.
+    pub(crate) fn call_params_value<R: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: &[serde_json::Value],
+    ) -> Result<Response<R>> {
+        let request = self.build_request(method, params);
+        let response = self.send_request(request)?;
+        if let Some(error) = response.error {
+            let params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+            return Err(command_error(method, &params, error));
+        }
+        Ok(response)
+    }
+
+    /// Calls a method with some arguments and returns the result, dispatching any notification
+    /// (i.e: message with a method and a null id) received in the meantime to the provided
+    /// callback.
+    ///
+    /// If the callback returns some value, it is sent back to the peer as the result of a
+    /// response with a null id.
+    pub(crate) fn call_interactive<R, P, F>(
+        &mut self,
+        method: &str,
+        params: &[P],
+        mut on_notification: F,
+    ) -> Result<Response<R>>
+    where
+        R: DeserializeOwned,
+        P: Serialize + AsRef<str>,
+        F: FnMut(serde_json::Value) -> Option<Vec<String>>,
+    {
+        let start = time::Instant::now();
+        let request = self.build_request(method, params);
+        let req_id = request.id.clone();
+        let stream = self.active_stream()?;
+        let bytes = stream.bytes();
+
+        let response = (|| -> Result<Response<R>> {
+            stream.send(request)?;
+            loop {
+                let msg: serde_json::Value = stream.recv().map_err(|e| closed_error(e, method))?;
+                if msg["id"].is_null() && msg.get("method").is_some() {
+                    if let Some(reply) = on_notification(msg) {
+                        stream.send(Response {
+                            result: Some(reply),
+                            error: None,
+                            id: RpcId::Null,
+                        })?;
+                    }
+                    continue;
+                }
+                if is_stale(&serde_json::from_value(msg["id"].clone())?, &req_id) {
+                    continue;
+                }
+                break Ok(serde_json::from_value(msg)?);
+            }
+        })();
+        self.record(
+            method,
+            start,
+            bytes,
+            response.as_ref().is_ok_and(|r| r.error.is_none()),
+        );
+        let response = response.map_err(|e| peer_error(&self.stream_client, e))?;
+        self.last_activity = time::Instant::now();
+
+        // Errors about requests the peer couldn't identify, e.g: malformed ones, have a null id.
+        if !matches!((&response.id, &response.error), (RpcId::Null, Some(_))) {
+            check_id(&response.id, &req_id)?;
+        }
+        if let Some(error) = response.error {
+            return Err(command_error(method, params, error));
+        }
+        Ok(response)
+    }
+
+    /// Calls a method whose result is split across several responses sharing the request id, and
+    /// returns the concatenation of their results.
+    ///
+    /// Responses are read until one with an empty (or null) result is received, which terminates
+    /// the result.
+    pub(crate) fn call_streaming<P: Serialize + AsRef<str>>(
+        &mut self,
+        method: &str,
+        params: &[P],
+    ) -> Result<String> {
+        let start = time::Instant::now();
+        let request = self.build_request(method, params);
+        let req_id = request.id.clone();
+        let stream = self.active_stream()?;
+        let bytes = stream.bytes();
+
+        let result = (|| -> Result<String> {
+            stream.send(request)?;
+            let mut result = String::new();
+            loop {
+                let res: Response<Box<RawValue>> =
+                    stream.recv().map_err(|e| closed_error(e, method))?;
+                if is_stale(&res.id, &req_id) {
+                    continue;
+                }
+                check_id(&res.id, &req_id)?;
+                let res: Response<String> = res.decode()?;
+                if let Some(error) = res.error {
+                    return Err(command_error(method, params, error));
+                }
+                match res.result {
+                    Some(chunk) if !chunk.is_empty() => result.push_str(&chunk),
+                    _ => break Ok(result),
+                }
+            }
+        })();
+        self.record(method, start, bytes, result.is_ok());
+        let result = result.map_err(|e| peer_error(&self.stream_client, e))?;
+        self.last_activity = time::Instant::now();
+        Ok(result)
+    }
+
+    /// Calls several methods in a single round-trip and returns their responses, in the same
+    /// order as the calls.
+    ///
+    /// All the requests are sent before reading any response. Responses are matched to their
+    /// request using their id. Errors returned by the peer are not converted, it's up to the
+    /// caller to inspect each response.
+    pub(crate) fn call_batch<R: DeserializeOwned, P: Serialize + AsRef<str>>(
+        &mut self,
+        calls: &[(&str, &[P])],
+    ) -> Result<Vec<Response<R>>> {
+        let requests: Vec<Request<P>> = calls
+            .iter()
+            .map(|(method, params)| self.build_request(method, params))
+            .collect();
+        let ids: Vec<RpcId> = requests.iter().map(|r| r.id.clone()).collect();
+
+        let start = time::Instant::now();
+        let stream = self.active_stream()?;
+        let bytes = stream.bytes();
+
+        let mut responses: Vec<Option<Response<R>>> = calls.iter().map(|_| None).collect();
+        let res = (|| -> Result<()> {
+            for request in requests {
+                stream.send(request)?;
+            }
+            let mut received = 0;
+            while received < calls.len() {
+                let res: Response<Box<RawValue>> =
+                    stream.recv().map_err(|e| closed_error(e, "batch"))?;
+                // Ids of a batch are consecutive, older ones are from previous requests.
+                if ids.first().is_some_and(|first| is_stale(&res.id, first)) {
+                    continue;
+                }
+                let id = match &res.id {
+                    RpcId::Null => {
+                        return Err(Error::Protocol("id not found in response".to_string()))
+                    }
+                    id => id.clone(),
+                };
+                let slot = ids
+                    .iter()
+                    .position(|i| *i == id)
+                    .and_then(|pos| responses.get_mut(pos))
+                    .filter(|slot| slot.is_none())
+                    .ok_or_else(|| Error::Protocol(format!("unexpected response id {id}")))?;
+                *slot = Some(res.decode()?);
+                received += 1;
+            }
+            Ok(())
+        })();
+
+        // Each call of the batch is reported with the latency of the whole batch. Bytes are only
+        // reported once.
+        let latency = start.elapsed();
+        for ((method, _), response) in calls.iter().zip(&responses) {
+            let success = response.as_ref().is_some_and(|r| r.error.is_none());
+            self.metrics.record_call(method, latency, success);
+        }
+        let (sent, received) = self.stream.bytes();
+        self.metrics.record_bytes(
+            sent.saturating_sub(bytes.0),
+            received.saturating_sub(bytes.1),
+        );
+        res.map_err(|e| peer_error(&self.stream_client, e))?;
+        self.last_activity = time::Instant::now();
+
+        // All the slots have been filled, as each response matched a distinct request.
+        Ok(responses.into_iter().flatten().collect())
+    }
+
+    /// Sends an arbitrary JSON message and returns the next message received, as is.
+    pub(crate) fn send_recv_raw(&mut self, msg: serde_json::Value) -> Result<serde_json::Value> {
+        let start = time::Instant::now();
+        let method = msg["method"].as_str().unwrap_or("raw").to_string();
+        let stream = self.active_stream()?;
+        let bytes = stream.bytes();
+
+        let res: Result<serde_json::Value> = stream
+            .send(&msg)
+            .and_then(|_| stream.recv().map_err(|e| closed_error(e, &method)));
+        self.record(
+            &method,
+            start,
+            bytes,
+            res.as_ref().is_ok_and(|r| r["error"].is_null()),
+        );
+        let res = res.map_err(|e| peer_error(&self.stream_client, e))?;
+        self.last_activity = time::Instant::now();
+        Ok(res)
+    }
+
+    /// Sends a request whose reply is followed by a stream of messages on the same connection,
+    /// e.g: the updates of an OVSDB "monitor", and returns the [`Subscription`] to read them.
+    ///
+    /// The subscription borrows the client: only one can be active per connection.
+    pub(crate) fn subscribe(
+        &mut self,
+        method: &str,
+        params: &[serde_json::Value],
+    ) -> Result<Subscription<'_, C>> {
+        let start = time::Instant::now();
+        let request = self.build_request(method, params);
+        let req_id = request.id.clone();
+        let stream = self.active_stream()?;
+        let bytes = stream.bytes();
+
+        if let Err(e) = stream.send(request) {
+            self.record(method, start, bytes, false);
+            return Err(peer_error(&self.stream_client, e));
+        }
+        Ok(Subscription {
+            client: self,
+            method: method.to_string(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+            json_params: params.to_vec(),
+            req_id,
+            start,
+            bytes,
+            replied: false,
+            done: false,
+        })
+    }
+
     /// Calls a method without arguments and resturns the result.
     pub(crate) fn call<R: DeserializeOwned>(&mut self, method: &str) -> Result<Response<R>> {
         let request = self.build_request::<&str>(method, &[]);
         let response = self.send_request(request)?;
         if let Some(error) = response.error {
-            return Err(Error::Command {
-                cmd: String::from(method),
-                params: String::default(),
-                error,
-            });
+            return Err(command_error::<&str>(method, &[], error));
         }
         Ok(response)
     }
 }
+
+/// Messages received after subscribing, see [`Client::subscribe`].
+///
+/// The first item is the result of the reply to the subscription request. The following ones are
+/// the messages received afterwards, as is. Timeouts are reported without ending the iteration, as
+/// they only mean nothing was received in the meantime; any other error ends it.
+pub(crate) struct Subscription<'a, C: JsonStreamClient> {
+    client: &'a mut Client<C>,
+    method: String,
+    /// Parameters, as strings for errors and as is to subscribe again.
+    params: Vec<String>,
+    json_params: Vec<serde_json::Value>,
+    req_id: RpcId,
+    /// Start time of the request and stream byte counters before it, reported with the reply.
+    start: time::Instant,
+    bytes: (u64, u64),
+    /// Whether the reply to the subscription request was received.
+    replied: bool,
+    /// Whether the iteration ended.
+    done: bool,
+}
+
+impl<C: JsonStreamClient> Subscription<'_, C> {
+    /// Reconnects, after the connection was lost with the given error, and sends the subscription
+    /// request again. The iteration starts over with the reply to the new request.
+    pub(crate) fn resubscribe(&mut self, error: &Error) -> Result<()> {
+        self.client
+            .reconnect(Some(error))
+            .inspect_err(|_| self.done = true)?;
+        self.start = time::Instant::now();
+        self.bytes = self.client.stream.bytes();
+        self.replied = false;
+        self.done = false;
+
+        let request = self.client.build_request(&self.method, &self.json_params);
+        self.req_id = request.id.clone();
+        if let Err(e) = self.client.stream.send(request) {
+            self.done = true;
+            self.client
+                .record(&self.method, self.start, self.bytes, false);
+            return Err(peer_error(&self.client.stream_client, e));
+        }
+        Ok(())
+    }
+
+    /// Receives the next item of the subscription.
+    fn recv(&mut self) -> Result<serde_json::Value> {
+        let msg: serde_json::Value = self
+            .client
+            .stream
+            .recv()
+            .map_err(|e| closed_error(e, &self.method))
+            .map_err(|e| peer_error(&self.client.stream_client, e))?;
+        self.client.last_activity = time::Instant::now();
+        if self.replied {
+            return Ok(msg);
+        }
+
+        let response: Response<serde_json::Value> = serde_json::from_value(msg)?;
+        self.replied = true;
+        self.client.record(
+            &self.method,
+            self.start,
+            self.bytes,
+            response.error.is_none(),
+        );
+        check_id(&response.id, &self.req_id)?;
+        if let Some(error) = response.error {
+            return Err(command_error(&self.method, &self.params, error));
+        }
+        Ok(response.result.unwrap_or_default())
+    }
+}
+
+impl<C: JsonStreamClient> Iterator for Subscription<'_, C> {
+    type Item = Result<serde_json::Value>;
+
+    fn next(&mut self) -> Option<Result<serde_json::Value>> {
+        if self.done {
+            return None;
+        }
+        let res = self.recv();
+        self.done = matches!(res, Err(ref e) if !matches!(e, Error::Timeout));
+        Some(res)
+    }
+}
+
+impl Client<Transport> {
+    /// Creates a new client with a Unix socket transport.
+    pub(crate) fn unix<P: AsRef<path::Path>>(
+        sock_path: P,
+        timeout: Option<time::Duration>,
+        connect_timeout: Option<time::Duration>,
+    ) -> Result<Client<Transport>> {
+        Client::new(Transport::Unix(unix_stream_client(
+            unix::UnixJsonStreamClient::new(sock_path),
+            timeout,
+            connect_timeout,
+        )))
+    }
+
+    /// Creates a new client with a Unix socket transport whose path is given by `resolve`, which
+    /// is called again on each reconnection, e.g: for the path to follow daemon restarts.
+    pub(crate) fn unix_resolved<F>(
+        mut resolve: F,
+        timeout: Option<time::Duration>,
+        connect_timeout: Option<time::Duration>,
+    ) -> Result<Client<Transport>>
+    where
+        F: FnMut() -> Result<path::PathBuf> + Send + 'static,
+    {
+        let mut client = Client::unix(resolve()?, timeout, connect_timeout)?;
+        client.resolver(Resolver(Box::new(move |_| {
+            Ok(Transport::Unix(unix_stream_client(
+                unix::UnixJsonStreamClient::new(resolve()?),
+                timeout,
+                connect_timeout,
+            )))
+        })));
+        Ok(client)
+    }
+
+    /// Creates a new client with a Unix socket transport, connecting to a socket in the abstract
+    /// namespace.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn unix_abstract(
+        name: &str,
+        timeout: Option<time::Duration>,
+        connect_timeout: Option<time::Duration>,
+    ) -> Result<Client<Transport>> {
+        Client::new(Transport::Unix(unix_stream_client(
+            unix::UnixJsonStreamClient::new_abstract(name),
+            timeout,
+            connect_timeout,
+        )))
+    }
+
+    /// Creates a new client with a Unix socket transport, using a socket given as a file
+    /// descriptor.
+    ///
+    /// # Safety
+    ///
+    /// See [`unix::UnixJsonStreamClient::from_raw_fd`].
+    pub(crate) unsafe fn unix_fd(
+        fd: RawFd,
+        timeout: Option<time::Duration>,
+    ) -> Result<Client<Transport>> {
+        Client::new(Transport::Unix(unix_stream_client(
+            // SAFETY: guaranteed by the caller.
+            unsafe { unix::UnixJsonStreamClient::from_raw_fd(fd) },
+            timeout,
+            None,
+        )))
+    }
+
+    /// Creates a new client with a TCP transport.
+    pub(crate) fn tcp<A: net::ToSocketAddrs>(
+        addr: A,
+        timeout: Option<time::Duration>,
+    ) -> Result<Client<Transport>> {
+        let mut stream_client = tcp::TcpJsonStreamClient::new(addr)?;
+        if let Some(timeout) = timeout {
+            stream_client = stream_client.timeout(timeout);
+        }
+        Client::new(Transport::Tcp(stream_client))
+    }
+
+    /// Creates a new client with a custom transport.
+    pub(crate) fn custom(transport: Box<dyn CustomTransport>) -> Result<Client<Transport>> {
+        Client::new(Transport::Custom(transport))
+    }
+}
+
+/// Returns whether a response id belongs to a request sent before the given one. Only the
+/// numeric ids this client generates can be compared this way.
+fn is_stale(id: &RpcId, req_id: &RpcId) -> bool {
+    matches!((id, req_id), (RpcId::Num(id), RpcId::Num(req_id)) if id < req_id)
+}
+
+/// Checks the id of a response matches the one of its request.
+fn check_id(id: &RpcId, req_id: &RpcId) -> Result<()> {
+    match id {
+        RpcId::Null => Err(Error::Protocol("id not found in response".to_string())),
+        id if id != req_id => Err(Error::Protocol(
+            "request and response ids do not match".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Attaches the address of the peer to socket errors, for them to tell which daemon failed.
+fn peer_error<C: fmt::Display>(peer: &C, error: Error) -> Error {
+    match error {
+        Error::Socket(source) => Error::SocketIo {
+            peer: peer.to_string(),
+            source,
+        },
+        e => e,
+    }
+}
+
+/// Sets the timeouts of a Unix socket transport.
+fn unix_stream_client(
+    mut stream_client: unix::UnixJsonStreamClient,
+    timeout: Option<time::Duration>,
+    connect_timeout: Option<time::Duration>,
+) -> unix::UnixJsonStreamClient {
+    if let Some(timeout) = timeout {
+        stream_client = stream_client.timeout(timeout);
+    }
+    if let Some(timeout) = connect_timeout {
+        stream_client = stream_client.connect_timeout(timeout);
+    }
+    stream_client
+}
+
+/// Sends a single JSON message to the writer, adding the number of bytes written to `count`.
+///
+/// Writes interrupted by a signal are retried, see [`io::Write::write_all`].
+pub(crate) fn send_json<W, M>(mut writer: W, msg: M, count: &mut u64) -> Result<()>
+where
+    W: io::Write,
+    M: Serialize,
+{
+    let buf = serde_json::to_vec(&msg)?;
+    writer.write_all(&buf)?;
+    *count += buf.len() as u64;
+    Ok(())
+}
+
+/// Receives a single JSON message from the reader (blocking), adding the number of bytes read to
+/// `count`.
+///
+/// Failing to receive a whole message is reported as:
+/// - a socket error of kind [`io::ErrorKind::UnexpectedEof`] when the peer closed the connection
+///   cleanly, i.e: before sending anything;
+/// - [`Error::TruncatedResponse`] when part of a message was received, be it the peer closing the
+///   connection or the read timing out after it;
+/// - [`Error::Timeout`] when the read timed out before anything was received.
+pub(crate) fn recv_json<R, T>(reader: R, count: &mut u64) -> Result<T>
+where
+    R: io::Read,
+    T: for<'a> Deserialize<'a>,
+{
+    let start = *count;
+    let reader = CountingReader {
+        inner: reader,
+        count,
+    };
+    let res = Deserializer::from_reader(reader).into_iter().next();
+    let bytes_read = *count - start;
+    match res {
+        Some(Ok(resp)) => Ok(resp),
+        // Part of a message was received and the rest will never come.
+        Some(Err(e)) if e.is_eof() => Err(Error::TruncatedResponse { bytes_read }),
+        Some(Err(e)) => match Error::from(e) {
+            Error::Socket(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                // Part of the message was received already, the rest is lost for good.
+                if bytes_read > 0 {
+                    Err(Error::TruncatedResponse { bytes_read })
+                } else {
+                    Err(Error::Timeout)
+                }
+            }
+            e => Err(e),
+        },
+        // The peer closed the connection.
+        None => Err(Error::Socket(io::ErrorKind::UnexpectedEof.into())),
+    }
+}
+
+/// Receives a single JSON message (blocking) as [`recv_json`] does, starting with the bytes
+/// already read by [`try_recv_json`], if any.
+pub(crate) fn recv_json_pending<R, T>(
+    reader: R,
+    pending: &mut Vec<u8>,
+    count: &mut u64,
+) -> Result<T>
+where
+    R: io::Read,
+    T: for<'a> Deserialize<'a>,
+{
+    if pending.is_empty() {
+        return recv_json(reader, count);
+    }
+    let mut buffered = io::Cursor::new(std::mem::take(pending));
+    let res = recv_json(io::Read::chain(&mut buffered, reader), count);
+    let consumed = buffered.position() as usize;
+    *pending = buffered.into_inner().split_off(consumed);
+    res
+}
+
+/// Receives a single JSON message from the reader if one was fully received already, adding the
+/// number of bytes of the message to `count`. The reader must be non-blocking.
+///
+/// All the available bytes are read into `pending`, which keeps those of incomplete messages for
+/// the next receive, be it blocking or not (see [`recv_json_pending`]).
+pub(crate) fn try_recv_json<R, T>(
+    reader: &mut io::BufReader<R>,
+    pending: &mut Vec<u8>,
+    count: &mut u64,
+) -> Result<Option<T>>
+where
+    R: io::Read,
+    T: for<'a> Deserialize<'a>,
+{
+    let mut eof = false;
+    loop {
+        let buf = match io::BufRead::fill_buf(reader) {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Error::Socket(e)),
+        };
+        if buf.is_empty() {
+            eof = true;
+            break;
+        }
+        let n = buf.len();
+        pending.extend_from_slice(buf);
+        io::BufRead::consume(reader, n);
+    }
+
+    let mut msgs = Deserializer::from_slice(pending).into_iter();
+    match msgs.next() {
+        Some(Ok(msg)) => {
+            let consumed = msgs.byte_offset();
+            pending.drain(..consumed);
+            *count += consumed as u64;
+            Ok(Some(msg))
+        }
+        // The rest of the message wasn't received yet.
+        Some(Err(e)) if e.is_eof() && !eof => Ok(None),
+        None if !eof => Ok(None),
+        // The peer closed the connection, possibly in the middle of a message.
+        Some(Err(e)) if e.is_eof() => Err(Error::TruncatedResponse {
+            bytes_read: pending.len() as u64,
+        }),
+        None => Err(Error::Socket(io::ErrorKind::UnexpectedEof.into())),
+        Some(Err(e)) => Err(e.into()),
+    }
+}
+
+/// Reader counting the bytes read from the inner reader.
+///
+/// Reads interrupted by a signal are retried, e.g: in processes receiving SIGCHLD from their
+/// children, instead of failing the whole message.
+struct CountingReader<'a, R> {
+    inner: R,
+    count: &'a mut u64,
+}
+
+impl<R: io::Read> io::Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = loop {
+            match self.inner.read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                res => break res?,
+            }
+        };
+        *self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// Builds the error returned when the peer replies to a command with an error.
+///
+/// Errors reporting that the command doesn't exist are turned into [`Error::UnknownCommand`].
+/// Unixctl servers report them as `"<cmd>" is not a valid command (use "list-commands" to see a
+/// list of valid commands)`, some other tools as `unknown command '<cmd>'; use --help ...`.
+pub(crate) fn command_error<P: AsRef<str>>(method: &str, params: &[P], error: String) -> Error {
+    if error.contains("is not a valid command") || error.starts_with("unknown command") {
+        return Error::UnknownCommand {
+            cmd: method.to_string(),
+        };
+    }
+    Error::Command {
+        cmd: String::from(method),
+        params: params
+            .iter()
+            .map(|p| p.as_ref())
+            .collect::<Vec<&str>>()
+            .join(", "),
+        error,
+    }
+}
+
+/// Maps an unexpected end of stream, i.e: the peer closing the connection, into a DaemonClosed
+/// error.
+fn closed_error(error: Error, method: &str) -> Error {
+    match error {
+        Error::Socket(e) if e.kind() == io::ErrorKind::UnexpectedEof => Error::DaemonClosed {
+            cmd: method.to_string(),
+        },
+        e => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_response() {
+        let msg = br#"{"result": "ok", "error": null, "id": 1}"#;
+        let mut count = 0;
+        let resp: Response = recv_json(&msg[..], &mut count).unwrap();
+        assert_eq!(resp.result.as_deref(), Some("ok"));
+        assert_eq!(count, msg.len() as u64);
+
+        let mut count = 0;
+        assert!(matches!(
+            recv_json::<_, Response>(&msg[..20], &mut count),
+            Err(Error::TruncatedResponse { bytes_read: 20 })
+        ));
+
+        assert!(matches!(
+            recv_json::<_, Response>(&b""[..], &mut count),
+            Err(Error::Socket(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn rpc_id() {
+        for (msg, id) in [
+            (r#"{"result": "ok", "id": 1}"#, RpcId::Num(1)),
+            (
+                r#"{"result": "ok", "id": "a"}"#,
+                RpcId::Str("a".to_string()),
+            ),
+            (r#"{"result": "ok", "id": null}"#, RpcId::Null),
+            (r#"{"result": "ok"}"#, RpcId::Null),
+        ] {
+            let resp: Response = serde_json::from_str(msg).unwrap();
+            assert_eq!(resp.id, id, "{msg}");
+        }
+
+        assert!(check_id(&RpcId::Num(1), &RpcId::Num(1)).is_ok());
+        assert!(check_id(&RpcId::Str("1".to_string()), &RpcId::Num(1)).is_err());
+        assert!(check_id(&RpcId::Null, &RpcId::Null).is_err());
+        assert_eq!(
+            serde_json::to_string(&[RpcId::Num(1), RpcId::Str("a".to_string()), RpcId::Null])
+                .unwrap(),
+            r#"[1,"a",null]"#
+        );
+    }
+
+    #[test]
+    fn interrupted_read() {
+        // Reader failing with EINTR before each successful read.
+        struct Interrupting<'a> {
+            data: &'a [u8],
+            interrupt: bool,
+        }
+
+        impl io::Read for Interrupting<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.interrupt = !self.interrupt;
+                match self.interrupt {
+                    true => Err(io::ErrorKind::Interrupted.into()),
+                    false => self.data.read(buf),
+                }
+            }
+        }
+
+        let msg = br#"{"result": "ok", "error": null, "id": 1}"#;
+        let mut count = 0;
+        let reader = Interrupting {
+            data: &msg[..],
+            interrupt: false,
+        };
+        let resp: Response = recv_json(reader, &mut count).unwrap();
+        assert_eq!(resp.result.as_deref(), Some("ok"));
+        assert_eq!(count, msg.len() as u64);
+    }
+}
@@ -0,0 +1,92 @@
+//! LACP helpers.
+
+use std::slice;
+
+use crate::{fdb::MacAddr, parse, OvsUnixCtl, Result};
+
+/// The LACP details of a bond, as shown by "lacp/show".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LacpInfo {
+    /// Name of the bond.
+    pub bond: String,
+    /// Whether LACP is in active mode, i.e: sends LACP PDUs on its own. Otherwise, it is passive.
+    pub active: bool,
+    /// Whether LACP was negotiated with the partner. Otherwise, the bond runs with its
+    /// configured settings.
+    pub negotiated: bool,
+    /// System id of the local system.
+    pub sys_id: MacAddr,
+    /// System priority of the local system.
+    pub sys_priority: u16,
+    /// Members (a.k.a slaves) of the bond.
+    pub members: Vec<LacpMember>,
+}
+
+/// The LACP details of a bond member (a.k.a slave).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LacpMember {
+    /// Name of the member.
+    pub name: String,
+    /// Status of the partner information, e.g: `current`, `expired` or `defaulted`.
+    pub status: String,
+    /// Whether the member is attached to the aggregator.
+    pub attached: bool,
+    /// Whether the member may be enabled.
+    pub may_enable: bool,
+    /// LACP information of the local end.
+    pub actor: LacpActor,
+    /// LACP information of the remote end.
+    pub partner: LacpActor,
+}
+
+/// The LACP information of one end of a link.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LacpActor {
+    /// System id.
+    pub sys_id: MacAddr,
+    /// System priority.
+    pub sys_priority: u16,
+    /// Port id.
+    pub port_id: u16,
+    /// Port priority.
+    pub port_priority: u16,
+    /// Aggregation key.
+    pub key: u16,
+    /// State flags.
+    pub state: LacpState,
+}
+
+/// The state flags of one end of a LACP link.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LacpState {
+    /// Active mode, as opposed to passive.
+    pub activity: bool,
+    /// Short timeout, as opposed to long.
+    pub timeout: bool,
+    /// The link can be aggregated.
+    pub aggregation: bool,
+    /// The link is in sync with the partner.
+    pub synchronization: bool,
+    /// Collection of incoming frames is enabled.
+    pub collecting: bool,
+    /// Distribution of outgoing frames is enabled.
+    pub distributing: bool,
+    /// The partner information is the default one, i.e: no LACP PDU was received.
+    pub defaulted: bool,
+    /// The partner information expired.
+    pub expired: bool,
+}
+
+impl OvsUnixCtl {
+    /// Runs the "lacp/show" command and returns the LACP details of the given port (bond), or
+    /// of all of them if `None`.
+    pub fn lacp_show(&mut self, port: Option<&str>) -> Result<Vec<LacpInfo>> {
+        // Nothing is printed when no port uses LACP.
+        let response = self.run("lacp/show", port.as_ref().map(slice::from_ref))?;
+        parse::lacp::show(&response.unwrap_or_default())
+    }
+}
@@ -16,11 +16,47 @@
 //! let bond0 = unixctl.run("bond/show", Some(&["bond0"])).unwrap();
 //! println!("{}", bond0.unwrap());
 //! ```
+//!
+//! The types of the command modules are re-exported at the root of the crate. The `memory` and
+//! `ovsdb` modules only add methods to [`OvsUnixCtl`], they have no types to re-export.
 
+pub mod bond;
+pub mod coverage;
+pub mod dpctl;
+pub mod dpif_netdev;
+pub mod fdb;
 mod jsonrpc;
+pub mod lacp;
+pub mod memory;
+pub mod metrics;
+pub mod ofproto;
+pub mod ovn;
 pub mod ovs;
+pub mod ovsdb;
+pub mod parse;
+pub mod route;
+pub mod shared;
+mod tcp;
+#[cfg(test)]
+mod testing;
+pub mod transport;
 mod unix;
+pub mod vlog;
+pub use bond::*;
+pub use coverage::*;
+pub use dpctl::*;
+pub use dpif_netdev::*;
+pub use fdb::*;
+pub use jsonrpc::{Request, Response, RpcId};
+pub use lacp::*;
+pub use metrics::*;
+pub use ofproto::*;
+pub use ovn::*;
 pub use ovs::*;
+pub use route::*;
+pub use shared::*;
+pub use transport::{CustomStream, CustomTransport};
+pub use vlog::*;
 
 pub mod error;
 pub use error::Error;
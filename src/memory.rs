@@ -0,0 +1,13 @@
+//! Memory usage helpers.
+
+use std::collections::BTreeMap;
+
+use crate::{parse, OvsUnixCtl, Result};
+
+impl OvsUnixCtl {
+    /// Runs the "memory/show" command and returns the memory usage figures reported by the
+    /// daemon, e.g: the number of handlers, ports or rules, indexed by name.
+    pub fn memory_show(&mut self) -> Result<BTreeMap<String, u64>> {
+        parse::memory::show(&self.run_output("memory/show", None)?)
+    }
+}
@@ -0,0 +1,30 @@
+//! Telemetry hooks.
+//!
+//! Implement [`Metrics`] to export the calls made to the daemon to any metrics backend, and set it
+//! using [`OvsUnixCtl::with_metrics`](crate::OvsUnixCtl::with_metrics).
+
+use std::{fmt, time::Duration};
+
+/// Receiver of telemetry about the calls made to the daemon.
+///
+/// All methods do nothing by default.
+pub trait Metrics: Send + Sync {
+    /// Records a call to a method, the time it took to get its response and whether it
+    /// succeeded, i.e: neither the transport nor the daemon reported an error.
+    fn record_call(&self, _method: &str, _latency: Duration, _success: bool) {}
+
+    /// Records the number of bytes sent and received during a call.
+    fn record_bytes(&self, _sent: u64, _recv: u64) {}
+}
+
+impl fmt::Debug for dyn Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Metrics")
+    }
+}
+
+/// Metrics implementation discarding everything, used by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
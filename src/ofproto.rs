@@ -0,0 +1,482 @@
+//! OpenFlow switch (ofproto) helpers.
+
+use crate::{error::Error, parse, OvsUnixCtl, Result};
+
+/// Result of an "ofproto/trace" run.
+///
+/// Sections are kept as printed by OVS. When the trace recirculates, the final flow, megaflow and
+/// datapath actions are the ones of the last pass.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceResult {
+    /// The traced flow.
+    pub flow: String,
+    /// The OpenFlow tables and rules the flow went through, for each bridge it traversed.
+    pub bridge: String,
+    /// The flow after all modifications, or `unchanged`.
+    pub final_flow: String,
+    /// The megaflow installed in the datapath for this flow.
+    pub megaflow: String,
+    /// The actions executed in the datapath.
+    pub datapath_actions: String,
+    /// The OpenFlow tables visited, in order, across all passes.
+    pub tables: Vec<TraceTable>,
+    /// The steps from a table to another one, i.e: resubmits and recirculations, referring to
+    /// the tables by their index in [`TraceResult::tables`].
+    pub steps: Vec<TraceStep>,
+}
+
+/// A visit of an OpenFlow table during a trace.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceTable {
+    /// Bridge the table belongs to.
+    pub bridge: String,
+    /// Table number.
+    pub table: u32,
+    /// The matching rule, e.g: `ip, priority 32768`, or `No match.`.
+    pub rule: String,
+    /// The actions executed by the rule, as printed by OVS. Notes (`->` and `>>>>` lines) are
+    /// left out.
+    pub actions: Vec<String>,
+    /// Pass of the trace the visit belongs to, incremented on each recirculation.
+    pub pass: usize,
+}
+
+/// Kind of a [`TraceStep`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TraceStepKind {
+    /// The packet was resubmitted to another table, e.g: by `resubmit` or `goto_table`.
+    #[default]
+    Resubmit,
+    /// The packet was recirculated and processing resumed in a new pass.
+    Recirc,
+}
+
+/// A step of a trace from a table visit to another one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceStep {
+    /// Kind of step.
+    pub kind: TraceStepKind,
+    /// Index of the table visit the step starts from.
+    pub from: usize,
+    /// Index of the table visit the step leads to.
+    pub to: usize,
+    /// For resubmits, the actions executed by the first visit up to the resubmit. For
+    /// recirculations, the header of the new pass, e.g: `recirc(0x1) - resume conntrack ...`.
+    pub actions: Vec<String>,
+}
+
+/// A trace as a directed graph of table visits, see [`TraceResult::to_graph`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceGraph {
+    /// The table visits.
+    pub nodes: Vec<TraceNode>,
+    /// The steps between table visits.
+    pub edges: Vec<TraceEdge>,
+}
+
+/// A node of a [`TraceGraph`], i.e: a table visit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceNode {
+    /// Identifier of the node, its index in [`TraceGraph::nodes`].
+    pub id: usize,
+    /// Bridge and table of the visit along with the matching rule.
+    pub label: String,
+}
+
+/// An edge of a [`TraceGraph`], i.e: a resubmit or a recirculation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceEdge {
+    /// Identifier of the node the edge starts from.
+    pub from: usize,
+    /// Identifier of the node the edge leads to.
+    pub to: usize,
+    /// Kind of step.
+    pub kind: TraceStepKind,
+    /// The actions executed along the edge, one per line.
+    pub label: String,
+}
+
+impl TraceResult {
+    /// Returns the trace as a directed graph whose nodes are the table visits and edges the
+    /// resubmits and recirculations.
+    pub fn to_graph(&self) -> TraceGraph {
+        TraceGraph {
+            nodes: self
+                .tables
+                .iter()
+                .enumerate()
+                .map(|(id, t)| TraceNode {
+                    id,
+                    label: format!("{} table {}\n{}", t.bridge, t.table, t.rule),
+                })
+                .collect(),
+            edges: self
+                .steps
+                .iter()
+                .map(|step| TraceEdge {
+                    from: step.from,
+                    to: step.to,
+                    kind: step.kind,
+                    label: match step.kind {
+                        TraceStepKind::Resubmit => step.actions.join("\n"),
+                        // Only keep the recirculation id, e.g: "recirc(0x1)".
+                        TraceStepKind::Recirc => step
+                            .actions
+                            .iter()
+                            .filter_map(|a| a.split(" - ").next())
+                            .collect::<Vec<&str>>()
+                            .join("\n"),
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the trace as a graph in the DOT format used by Graphviz, see
+    /// [`TraceResult::to_graph`].
+    pub fn to_dot(&self) -> String {
+        self.to_graph().to_dot()
+    }
+}
+
+impl TraceGraph {
+    /// Returns the graph in the DOT format used by Graphviz. Recirculations are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("digraph trace {\n");
+        for node in &self.nodes {
+            dot.push_str(&format!(
+                "    t{} [shape=box, label=\"{}\"];\n",
+                node.id,
+                escape(&node.label).replace('\n', "\\n")
+            ));
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                TraceStepKind::Resubmit => "",
+                TraceStepKind::Recirc => ", style=dashed",
+            };
+            dot.push_str(&format!(
+                "    t{} -> t{} [label=\"{}\"{style}];\n",
+                edge.from,
+                edge.to,
+                escape(&edge.label).replace('\n', "\\n")
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// A bridge, as shown by "dpif/show".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DpifBridge {
+    /// Type of the datapath the bridge belongs to, e.g: `system` or `netdev`.
+    pub datapath_type: String,
+    /// Name of the bridge.
+    pub bridge: String,
+    /// Number of flows of the bridge, only reported by older versions.
+    pub flow_count: Option<u64>,
+    /// Ports of the bridge.
+    pub ports: Vec<DpifPort>,
+}
+
+/// A bridge port, as shown by "dpif/show".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DpifPort {
+    /// Name of the port.
+    pub name: String,
+    /// OpenFlow port number.
+    pub port_no: u32,
+    /// Datapath port number, `None` if the port isn't in the datapath.
+    pub dp_port_no: Option<u32>,
+    /// Port type (and configuration), e.g: `internal` or `vxlan: remote_ip=10.0.0.1`.
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub type_: String,
+}
+
+/// Session to run several traces against the same bridge.
+///
+/// Obtained through [`OvsUnixCtl::ofproto_trace_session`].
+#[derive(Debug)]
+pub struct TraceSession<'a> {
+    unixctl: &'a mut OvsUnixCtl,
+    bridge: String,
+}
+
+impl TraceSession<'_> {
+    /// Name of the bridge traces are run against.
+    pub fn bridge(&self) -> &str {
+        &self.bridge
+    }
+
+    /// Runs the "ofproto/trace" command for the given flow, e.g: `in_port=1,icmp`, and returns
+    /// the parsed trace.
+    pub fn trace(&mut self, flow: &str) -> Result<TraceResult> {
+        parse::ofproto::trace(
+            &self
+                .unixctl
+                .run_output("ofproto/trace", Some(&[&self.bridge, flow]))?,
+        )
+    }
+}
+
+impl OvsUnixCtl {
+    /// Runs the "dpif/show" command and returns the bridges, along with the datapath they belong
+    /// to and their ports.
+    pub fn dpif_show(&mut self) -> Result<Vec<DpifBridge>> {
+        parse::ofproto::dpif_show(&self.run("dpif/show", None)?.unwrap_or_default())
+    }
+
+    /// Returns the names of the bridges, as listed by the "ofproto/list" command.
+    pub fn ofproto_list(&mut self) -> Result<Vec<String>> {
+        Ok(self
+            .run("ofproto/list", None)?
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Runs the "ofproto/trace" command for the given flow against the bridge and returns the
+    /// parsed trace.
+    ///
+    /// If a packet is given, it is traced instead of a packet generated from the flow, whose
+    /// fields not present in the packet (e.g: the input port) are still used. It is passed
+    /// encoded using [`encode_binary_param`](crate::encode_binary_param).
+    pub fn ofproto_trace(
+        &mut self,
+        bridge: &str,
+        flow: &str,
+        packet: Option<&[u8]>,
+    ) -> Result<TraceResult> {
+        let response = match packet {
+            Some(packet) => self.run_with_binary("ofproto/trace", &[bridge, flow], packet)?,
+            None => self.run("ofproto/trace", Some(&[bridge, flow]))?,
+        };
+        parse::ofproto::trace(&response.unwrap_or_default())
+    }
+
+    /// Runs the "ofproto/trace" command for each of the given flows against the bridge, in a
+    /// single round-trip to the daemon (see [`OvsUnixCtl::run_batch`]).
+    ///
+    /// The result of each trace is returned at the same position as its flow; a failing trace
+    /// doesn't affect the others.
+    pub fn trace_batch(
+        &mut self,
+        bridge: &str,
+        flows: &[&str],
+    ) -> Result<Vec<Result<TraceResult>>> {
+        let params: Vec<[&str; 2]> = flows.iter().map(|flow| [bridge, flow]).collect();
+        let commands: Vec<(&str, &[&str])> = params
+            .iter()
+            .map(|params| ("ofproto/trace", &params[..]))
+            .collect();
+        Ok(self
+            .run_batch(&commands)?
+            .into_iter()
+            .map(|res| parse::ofproto::trace(&res?.unwrap_or_default()))
+            .collect())
+    }
+
+    /// Creates a session to run several traces against the given bridge.
+    ///
+    /// The bridge is checked to exist once, when the session is created, instead of each trace
+    /// failing on its own. Unixctl doesn't expose the OpenFlow table layout of a bridge, so there
+    /// is nothing else to retrieve upfront.
+    pub fn ofproto_trace_session(&mut self, bridge: &str) -> Result<TraceSession<'_>> {
+        if !self.ofproto_list()?.iter().any(|b| b == bridge) {
+            return Err(Error::InvalidArgument(format!("no such bridge: {bridge}")));
+        }
+        Ok(TraceSession {
+            unixctl: self,
+            bridge: bridge.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeServer;
+
+    fn fake_trace(bridge: &str, flow: &str) -> String {
+        format!(
+            "Flow: {flow}
+
+bridge(\"{bridge}\")
+-------------
+ 0. priority 0
+    NORMAL
+
+Final flow: unchanged
+Megaflow: recirc_id=0,eth,in_port=1,dl_type=0x0000
+Datapath actions: {}
+",
+            flow.len()
+        )
+    }
+
+    #[test]
+    fn trace_graph() {
+        let trace = TraceResult {
+            tables: [
+                (0, "in_port=1, priority 32768"),
+                (1, "priority 0"),
+                (2, "priority 0"),
+            ]
+            .iter()
+            .enumerate()
+            .map(|(i, (table, rule))| TraceTable {
+                bridge: "br0".to_string(),
+                table: *table,
+                rule: rule.to_string(),
+                pass: i / 2,
+                ..Default::default()
+            })
+            .collect(),
+            steps: vec![
+                TraceStep {
+                    kind: TraceStepKind::Resubmit,
+                    from: 0,
+                    to: 1,
+                    actions: vec![
+                        "set_field:0x1->reg0".to_string(),
+                        "resubmit(,1)".to_string(),
+                    ],
+                },
+                TraceStep {
+                    kind: TraceStepKind::Recirc,
+                    from: 1,
+                    to: 2,
+                    actions: vec!["recirc(0x1) - resume conntrack".to_string()],
+                },
+            ],
+            ..Default::default()
+        };
+
+        let graph = trace.to_graph();
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!(graph.nodes[1].label, "br0 table 1\npriority 0");
+        assert_eq!(graph.edges[0].label, "set_field:0x1->reg0\nresubmit(,1)");
+        assert_eq!(graph.edges[1].label, "recirc(0x1)");
+
+        assert_eq!(
+            trace.to_dot(),
+            r#"digraph trace {
+    t0 [shape=box, label="br0 table 0\nin_port=1, priority 32768"];
+    t1 [shape=box, label="br0 table 1\npriority 0"];
+    t2 [shape=box, label="br0 table 2\npriority 0"];
+    t0 -> t1 [label="set_field:0x1->reg0\nresubmit(,1)"];
+    t1 -> t2 [label="recirc(0x1)", style=dashed];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn trace_session() {
+        let server = FakeServer::new("trace_session", |method, params| match method {
+            "ofproto/list" => Ok("br0\nbr-int\n".to_string()),
+            "ofproto/trace" => Ok(fake_trace(&params[0], &params[1])),
+            _ => Err("unknown command".to_string()),
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert!(matches!(
+            ovs.ofproto_trace_session("br1"),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        let mut session = ovs.ofproto_trace_session("br0").unwrap();
+        assert_eq!(session.bridge(), "br0");
+        let first = session.trace("in_port=1").unwrap();
+        assert_eq!(first.flow, "in_port=1");
+        assert_eq!(first.datapath_actions, "9");
+        let second = session.trace("in_port=1,icmp").unwrap();
+        assert_eq!(second.flow, "in_port=1,icmp");
+        assert_eq!(second.datapath_actions, "14");
+        assert_eq!(
+            second.bridge,
+            "bridge(\"br0\")\n-------------\n 0. priority 0\n    NORMAL"
+        );
+        drop(ovs);
+
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[test]
+    fn ofproto_trace() {
+        let server = FakeServer::new("ofproto_trace", |_, params| {
+            Ok(fake_trace(&params[0], &params[1..].join(" ")))
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let trace = ovs.ofproto_trace("br0", "in_port=1", None).unwrap();
+        assert_eq!(trace.flow, "in_port=1");
+        let trace = ovs
+            .ofproto_trace("br0", "in_port=1", Some(&[0xff, 0x00, 0x1a]))
+            .unwrap();
+        assert_eq!(trace.flow, "in_port=1 ff001a");
+        drop(ovs);
+
+        assert_eq!(
+            server.requests()[1],
+            (
+                "ofproto/trace".to_string(),
+                vec![
+                    "br0".to_string(),
+                    "in_port=1".to_string(),
+                    "ff001a".to_string()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn trace_batch() {
+        let server = FakeServer::new("trace_batch", |_, params| match params[1].as_str() {
+            "bad" => Err("ovs-appctl: invalid flow".to_string()),
+            flow => Ok(fake_trace(&params[0], flow)),
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let traces = ovs
+            .trace_batch("br0", &["in_port=1", "bad", "in_port=1,icmp"])
+            .unwrap();
+        drop(ovs);
+
+        assert_eq!(traces.len(), 3);
+        assert_eq!(traces[0].as_ref().unwrap().flow, "in_port=1");
+        assert!(matches!(traces[1], Err(Error::Command { .. })));
+        assert_eq!(traces[2].as_ref().unwrap().flow, "in_port=1,icmp");
+        assert_eq!(traces[2].as_ref().unwrap().datapath_actions, "14");
+        assert_eq!(
+            server.requests_per_connection(),
+            vec![vec![
+                (
+                    "ofproto/trace".to_string(),
+                    vec!["br0".to_string(), "in_port=1".to_string()]
+                ),
+                (
+                    "ofproto/trace".to_string(),
+                    vec!["br0".to_string(), "bad".to_string()]
+                ),
+                (
+                    "ofproto/trace".to_string(),
+                    vec!["br0".to_string(), "in_port=1,icmp".to_string()]
+                ),
+            ]]
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! OVN helpers
+//!
+//! Commands specific to OVN daemons. Connect to them using [`OvsUnixCtl::with_target`], e.g.:
+//! `OvsUnixCtl::with_target("ovn-controller".into(), None)`.
+
+use std::collections::HashMap;
+
+use crate::{parse, OvsUnixCtl, Result};
+
+/// Statistics of a node of ovn-controller's incremental processing engine.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EngineStat {
+    /// Number of times the node was fully recomputed.
+    pub recompute: u64,
+    /// Number of times the node was incrementally computed.
+    pub compute: u64,
+    /// Number of times the node processing was aborted (a.k.a canceled).
+    pub abort: u64,
+}
+
+/// Statistics of ovn-controller's logical flow cache.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LflowCacheStats {
+    /// Whether the cache is enabled.
+    pub enabled: bool,
+    /// Total number of entries in the cache.
+    pub entries: u64,
+    /// Memory used by the cache, in kB.
+    pub mem_usage_kb: u64,
+}
+
+impl OvsUnixCtl {
+    /// Runs ovn-controller's "ct-zone-list" command and returns the conntrack zone assigned to
+    /// each logical port.
+    pub fn ovn_ct_zone_list(&mut self) -> Result<HashMap<String, u16>> {
+        parse::ovn::ct_zone_list(&self.run_output("ct-zone-list", None)?)
+    }
+
+    /// Runs ovn-controller's "inc-engine/show-stats" command and returns the statistics of each
+    /// incremental processing engine node.
+    pub fn inc_engine_show_stats(&mut self) -> Result<HashMap<String, EngineStat>> {
+        parse::ovn::inc_engine_stats(&self.run_output("inc-engine/show-stats", None)?)
+    }
+
+    /// Runs ovn-controller's "recompute" command, forcing a full recompute of the incremental
+    /// processing engine.
+    pub fn ovn_recompute(&mut self) -> Result<()> {
+        self.run("recompute", None)?;
+        Ok(())
+    }
+
+    /// Runs ovn-controller's "lflow-cache/flush" command, flushing the logical flow cache.
+    pub fn ovn_lflow_cache_flush(&mut self) -> Result<()> {
+        self.run("lflow-cache/flush", None)?;
+        Ok(())
+    }
+
+    /// Runs ovn-controller's "lflow-cache/show-stats" command and returns the logical flow cache
+    /// statistics.
+    pub fn ovn_lflow_cache_stats(&mut self) -> Result<LflowCacheStats> {
+        parse::ovn::lflow_cache_stats(&self.run_output("lflow-cache/show-stats", None)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeServer;
+
+    #[test]
+    fn mutators_params() {
+        let server = FakeServer::new("ovn_mutators", |_, _| Ok(String::default()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        ovs.ovn_recompute().unwrap();
+        ovs.ovn_lflow_cache_flush().unwrap();
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![
+                ("recompute".to_string(), vec![]),
+                ("lflow-cache/flush".to_string(), vec![]),
+            ]
+        );
+    }
+}
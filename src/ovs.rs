@@ -1,23 +1,371 @@
 //! OVS unixctl interface
 
 use std::{
-    env, fs,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    env, fmt, fs,
+    net::ToSocketAddrs,
+    os::unix::io::{AsRawFd, RawFd},
     path::{Path, PathBuf},
-    time::Duration,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
-use crate::{error::Error, jsonrpc, unix, Result};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::Error,
+    jsonrpc::{self, Response, RpcId},
+    metrics::Metrics,
+    parse,
+    transport::{CustomTransport, Transport},
+    Result,
+};
 
 const DEFAULT_RUNDIR: &str = "/var/run/openvswitch";
 
+/// Names of the daemons known to expose a unixctl interface.
+const KNOWN_TARGETS: &[&str] = &[
+    "ovs-vswitchd",
+    "ovsdb-server",
+    "ovn-controller",
+    "ovn-controller-vtep",
+    "ovn-northd",
+    "ovn-ic",
+];
+
+/// Reconnection of an [`OvsUnixCtl`] to the daemon, see [`OvsUnixCtl::with_reconnect_callback`].
+#[derive(Debug)]
+pub struct ReconnectEvent<'a> {
+    /// Path of the socket used before the reconnection, `None` if not connected through a Unix
+    /// socket bound to a path.
+    pub old_path: Option<&'a Path>,
+    /// Path of the socket used after the reconnection, `None` if not connected through a Unix
+    /// socket bound to a path.
+    pub new_path: Option<&'a Path>,
+    /// Error that triggered the reconnection, if any.
+    pub error: Option<&'a Error>,
+}
+
+/// Version of an OVS daemon, as returned by [`OvsUnixCtl::version`].
+///
+/// Versions are compared on their major, minor and patch numbers only: the extra part (e.g:
+/// distribution specific suffixes) is ignored, including for equality.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OvsVersion {
+    /// Major version number.
+    pub major: u32,
+    /// Minor version number.
+    pub minor: u32,
+    /// Patch version number.
+    pub patch: u32,
+    /// What follows the patch number, without its leading separator, or empty.
+    pub extra: String,
+}
+
+impl OvsVersion {
+    /// Creates a new version without extra part, e.g: to compare a daemon version against it.
+    pub fn new(major: u32, minor: u32, patch: u32) -> OvsVersion {
+        OvsVersion {
+            major,
+            minor,
+            patch,
+            extra: String::default(),
+        }
+    }
+}
+
+impl PartialEq for OvsVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OvsVersion {}
+
+impl PartialOrd for OvsVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OvsVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
+    }
+}
+
+impl fmt::Display for OvsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.extra.is_empty() {
+            write!(f, "-{}", self.extra)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<(u32, u32, u32, String)> for OvsVersion {
+    fn from((major, minor, patch, extra): (u32, u32, u32, String)) -> OvsVersion {
+        OvsVersion {
+            major,
+            minor,
+            patch,
+            extra,
+        }
+    }
+}
+
+impl From<OvsVersion> for (u32, u32, u32, String) {
+    fn from(version: OvsVersion) -> (u32, u32, u32, String) {
+        (version.major, version.minor, version.patch, version.extra)
+    }
+}
+
+/// Differences between the commands supported by two daemons, see [`diff_commands`].
+///
+/// Commands are given as `(command, arguments)` tuples, sorted by command name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CommandDiff {
+    /// Commands only supported by the second daemon.
+    pub added: Vec<(String, String)>,
+    /// Commands only supported by the first daemon.
+    pub removed: Vec<(String, String)>,
+    /// Commands supported by both daemons with different arguments, as `(command, arguments of
+    /// the first daemon, arguments of the second one)` tuples.
+    pub changed_args: Vec<(String, String, String)>,
+}
+
+/// Messages received after subscribing, see [`OvsUnixCtl::subscribe`].
+///
+/// The first item is the result of the reply to the subscription request, the following ones are
+/// the messages the peer sends afterwards, as is. Timeouts are reported without ending the
+/// iteration, as they only mean nothing was received in the meantime; any other error ends it.
+pub struct Subscription<'a>(jsonrpc::Subscription<'a, Transport>);
+
+impl Iterator for Subscription<'_> {
+    type Item = Result<serde_json::Value>;
+
+    fn next(&mut self) -> Option<Result<serde_json::Value>> {
+        self.0.next()
+    }
+}
+
+/// Item of a [`Monitor`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MonitorEvent {
+    /// A message of the subscription, see [`Subscription`].
+    Message(serde_json::Value),
+    /// The connection was lost and a new subscription was made: messages sent in the meantime
+    /// were missed. The next message is the result of the reply to the new subscription request.
+    Reconnected,
+}
+
+/// Messages received after subscribing, surviving the loss of the connection, see
+/// [`OvsUnixCtl::monitor`].
+///
+/// Errors are reported as for a [`Subscription`], except the ones meaning the connection was
+/// lost when reconnecting is enabled: a new connection is made, the subscription request is sent
+/// again and [`MonitorEvent::Reconnected`] is returned. Failing to do so ends the iteration.
+pub struct Monitor<'a> {
+    subscription: jsonrpc::Subscription<'a, Transport>,
+    reconnect: bool,
+}
+
+impl Iterator for Monitor<'_> {
+    type Item = Result<MonitorEvent>;
+
+    fn next(&mut self) -> Option<Result<MonitorEvent>> {
+        match self.subscription.next()? {
+            Err(e) if self.reconnect && is_connection_lost(&e) => Some(
+                self.subscription
+                    .resubscribe(&e)
+                    .map(|_| MonitorEvent::Reconnected),
+            ),
+            res => Some(res.map(MonitorEvent::Message)),
+        }
+    }
+}
+
+/// The file descriptor is the one of the current connection: it changes on reconnections,
+/// including the ones caused by the idle timeout. Custom transports not exposing a file
+/// descriptor (see [`CustomStream::raw_fd`](crate::CustomStream::raw_fd)) return -1.
+impl AsRawFd for OvsUnixCtl {
+    fn as_raw_fd(&self) -> RawFd {
+        self.client.raw_fd().unwrap_or(-1)
+    }
+}
+
+/// Returns whether the error means the connection was lost, including the peer closing it.
+fn is_connection_lost(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::DaemonClosed { .. } | Error::TruncatedResponse { .. }
+    ) || error.is_connection_error()
+}
+
 /// OVS Unix control interface.
 ///
 /// It allows the execution of control commands against ovs-vswitchd.
 #[derive(Debug)]
 pub struct OvsUnixCtl {
-    // JSON-RPC client. For now, only Unix is supported. If more are supported in the future, this
-    // would have to be a generic type.
-    client: jsonrpc::Client<unix::UnixJsonStreamClient>,
+    // JSON-RPC client, over any of the supported transports.
+    client: jsonrpc::Client<Transport>,
+    // Name of the daemon, when looked up by name.
+    target: Option<String>,
+    // Results of the commands run through run_cached, along with the time they were fetched.
+    cache: HashMap<(String, Vec<String>), (Instant, Option<String>)>,
+    // Whether commands are intercepted instead of being sent, see with_dry_run.
+    dry_run: bool,
+    // Whether read-only commands are still sent in dry-run mode.
+    dry_run_reads: bool,
+}
+
+/// Builder of [`OvsUnixCtl`], see [`OvsUnixCtl::builder`].
+///
+/// By default, the client connects to ovs-vswitchd, finding its pidfile and socket in the
+/// default directory or in the one specified in the OVS_RUNDIR env variable.
+#[derive(Clone, Debug, Default)]
+pub struct OvsUnixCtlBuilder {
+    target: Option<String>,
+    rundir: Option<PathBuf>,
+    socket_path: Option<PathBuf>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    // Whether read-only commands are allowed, when the dry-run mode is enabled.
+    dry_run: Option<bool>,
+    reconnect: bool,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl OvsUnixCtlBuilder {
+    /// Sets the daemon to connect to, e.g.: ovs-vswitchd, ovsdb-server, northd, etc.
+    pub fn target(mut self, target: &str) -> OvsUnixCtlBuilder {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Sets the directory where the pidfile and socket of the target are looked up, overriding
+    /// the OVS_RUNDIR env variable.
+    pub fn rundir(mut self, rundir: PathBuf) -> OvsUnixCtlBuilder {
+        self.rundir = Some(rundir);
+        self
+    }
+
+    /// Sets the path of the unix socket to connect to. No lookup is done: the target and the
+    /// rundir are ignored.
+    pub fn socket_path(mut self, path: PathBuf) -> OvsUnixCtlBuilder {
+        self.socket_path = Some(path);
+        self
+    }
+
+    /// Sets the read and write timeout, 1s by default.
+    pub fn timeout(mut self, timeout: Duration) -> OvsUnixCtlBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout of the connection to the socket, none by default. It also applies to
+    /// reconnections.
+    ///
+    /// Connecting to a Unix socket only blocks when its listen backlog is full, e.g: if the daemon
+    /// is stuck, which only happens on Linux.
+    pub fn connect_timeout(mut self, timeout: Duration) -> OvsUnixCtlBuilder {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets an idle timeout, see [`OvsUnixCtl::with_idle_timeout`].
+    pub fn idle_timeout(mut self, timeout: Duration) -> OvsUnixCtlBuilder {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables the dry-run mode, see [`OvsUnixCtl::with_dry_run`].
+    pub fn dry_run(mut self, allow_read_only: bool) -> OvsUnixCtlBuilder {
+        self.dry_run = Some(allow_read_only);
+        self
+    }
+
+    /// Enables or disables the automatic reconnection, see [`OvsUnixCtl::with_reconnect`].
+    pub fn reconnect(mut self, enabled: bool) -> OvsUnixCtlBuilder {
+        self.reconnect = enabled;
+        self
+    }
+
+    /// Sets the receiver of telemetry about the calls made to the daemon, see
+    /// [`OvsUnixCtl::with_metrics`].
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> OvsUnixCtlBuilder {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Creates the OvsUnixCtl and connects to the daemon.
+    ///
+    /// If the target can't be found and its name is close to the one of a known daemon,
+    /// [`Error::UnknownTarget`] is returned with a suggestion. When looked up, the socket is
+    /// looked up again on reconnections, so the new one is used if the daemon was restarted.
+    pub fn build(self) -> Result<OvsUnixCtl> {
+        let timeout = self.timeout.or(Some(Duration::from_secs(1)));
+        let connect_timeout = self.connect_timeout;
+        let ovs = match self.socket_path {
+            Some(ref path) => OvsUnixCtl::unix_with_timeouts(path, timeout, connect_timeout)?,
+            None => self.lookup(timeout, connect_timeout)?,
+        };
+        Ok(self.configure(ovs))
+    }
+
+    /// Looks up the socket of the target and connects to it.
+    fn lookup(
+        &self,
+        timeout: Option<Duration>,
+        connect_timeout: Option<Duration>,
+    ) -> Result<OvsUnixCtl> {
+        let target = self
+            .target
+            .clone()
+            .unwrap_or_else(|| "ovs-vswitchd".to_string());
+        let rundir = self.rundir.clone().unwrap_or_else(rundir);
+        let client = jsonrpc::Client::unix_resolved(
+            {
+                let target = target.clone();
+                move || find_socket(&target, &rundir)
+            },
+            timeout,
+            connect_timeout,
+        )
+        .map_err(|e| match (&e, suggest_target(&target)) {
+            // Only a target that can't be found may be misspelled.
+            (Error::OvsNotRunning | Error::SocketNotFound(_), Some(suggestion)) => {
+                Error::UnknownTarget {
+                    target: target.clone(),
+                    suggestion,
+                }
+            }
+            _ => e,
+        })?;
+        Ok(OvsUnixCtl {
+            target: Some(target),
+            ..OvsUnixCtl::with_client(client)
+        })
+    }
+
+    /// Applies the options set on the connected OvsUnixCtl.
+    fn configure(self, mut ovs: OvsUnixCtl) -> OvsUnixCtl {
+        if let Some(timeout) = self.idle_timeout {
+            ovs = ovs.with_idle_timeout(timeout);
+        }
+        if let Some(allow_read_only) = self.dry_run {
+            ovs = ovs.with_dry_run(allow_read_only);
+        }
+        if let Some(metrics) = self.metrics {
+            ovs = ovs.with_metrics(metrics);
+        }
+        ovs.with_reconnect(self.reconnect)
+    }
 }
 
 impl OvsUnixCtl {
@@ -26,8 +374,11 @@ impl OvsUnixCtl {
     /// Tries to find the pidfile and socket in the default path or in the one specified in the
     /// OVS_RUNDIR env variable.
     pub fn new(timeout: Option<Duration>) -> Result<OvsUnixCtl> {
-        let sockpath = Self::find_socket("ovs-vswitchd".into())?;
-        Self::unix(sockpath, timeout)
+        OvsUnixCtlBuilder {
+            timeout,
+            ..Default::default()
+        }
+        .build()
     }
 
     /// Creates a new OvsUnixCtl against the provided target, e.g.: ovs-vswitchd, ovsdb-server,
@@ -35,13 +386,74 @@ impl OvsUnixCtl {
     ///
     /// Tries to find the pidfile and socket in the default path or in the one specified in the
     /// OVS_RUNDIR env variable.
+    ///
+    /// If the target can't be found and its name is close to the one of a known daemon,
+    /// [`Error::UnknownTarget`] is returned with a suggestion.
     pub fn with_target(target: String, timeout: Option<Duration>) -> Result<OvsUnixCtl> {
-        let sockpath = Self::find_socket(target)?;
-        Self::unix(sockpath, timeout)
+        Self::with_target_in(target, rundir(), timeout)
+    }
+
+    /// Creates a new OvsUnixCtl against the provided target, finding its pidfile and socket in
+    /// the given directory. Unlike [`OvsUnixCtl::with_target`], the OVS_RUNDIR env variable is
+    /// not used, which allows talking to OVS instances running in different directories.
+    ///
+    /// If the target can't be found and its name is close to the one of a known daemon,
+    /// [`Error::UnknownTarget`] is returned with a suggestion.
+    pub fn with_target_in<P: AsRef<Path>>(
+        target: String,
+        rundir: P,
+        timeout: Option<Duration>,
+    ) -> Result<OvsUnixCtl> {
+        OvsUnixCtlBuilder {
+            target: Some(target),
+            rundir: Some(rundir.as_ref().to_path_buf()),
+            timeout,
+            ..Default::default()
+        }
+        .build()
+    }
+
+    /// Finds the daemons running in the given directory and returns their names along with the
+    /// path to their socket, sorted by name.
+    ///
+    /// Daemons are found through their pidfile, `{target}.pid`, and must have the matching
+    /// socket, `{target}.{pid}.ctl`. Stale pidfiles, i.e: without a socket, are skipped.
+    pub fn discover<P: AsRef<Path>>(rundir: P) -> Result<Vec<(String, PathBuf)>> {
+        let rundir = rundir.as_ref();
+        let mut daemons = Vec::new();
+        for entry in fs::read_dir(rundir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "pid") {
+                continue;
+            }
+            let Some(target) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if let Ok(sock_path) = find_socket(target, rundir) {
+                daemons.push((target.to_string(), sock_path));
+            }
+        }
+        daemons.sort();
+        Ok(daemons)
+    }
+
+    /// Returns a builder allowing to combine the construction options, e.g: a target looked up in
+    /// a custom directory with a custom timeout.
+    pub fn builder() -> OvsUnixCtlBuilder {
+        OvsUnixCtlBuilder::default()
     }
 
     /// Creates a new OvsUnixCtl by specifing a concrete unix socket path.
     pub fn unix<P: AsRef<Path>>(path: P, timeout: Option<Duration>) -> Result<OvsUnixCtl> {
+        let timeout = timeout.or(Some(Duration::from_secs(1)));
+        Self::unix_with_timeouts(path, timeout, None)
+    }
+
+    fn unix_with_timeouts<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        connect_timeout: Option<Duration>,
+    ) -> Result<OvsUnixCtl> {
         if !path.as_ref().exists() {
             return Err(Error::SocketNotFound(format!(
                 "{}",
@@ -49,210 +461,821 @@ impl OvsUnixCtl {
             )));
         }
 
-        Ok(Self {
-            client: jsonrpc::Client::<unix::UnixJsonStreamClient>::unix(
-                path,
-                timeout.or(Some(Duration::from_secs(1))),
-            )?,
-        })
+        Ok(Self::with_client(jsonrpc::Client::unix(
+            path,
+            timeout,
+            connect_timeout,
+        )?))
     }
 
-    fn find_socket_at<P: AsRef<Path>>(target: &str, rundir: P) -> Result<PathBuf> {
-        // Find $OVS_RUNDIR/{target}.pid
-        let pidfile_path = rundir.as_ref().join(format!("{}.pid", &target));
-        let pid_str = fs::read_to_string(pidfile_path.clone()).map_err(|_| Error::OvsNotRunning)?;
-        let pid_str = pid_str.trim();
+    /// Creates a new OvsUnixCtl by specifying the name of a socket in the Linux abstract
+    /// namespace, without its leading NUL byte.
+    #[cfg(target_os = "linux")]
+    pub fn unix_abstract(name: &str, timeout: Option<Duration>) -> Result<OvsUnixCtl> {
+        let timeout = timeout.or(Some(Duration::from_secs(1)));
+        Ok(Self::with_client(jsonrpc::Client::unix_abstract(
+            name, timeout, None,
+        )?))
+    }
 
-        if pid_str.is_empty() {
-            return Err(Error::OvsNotRunning);
-        }
+    /// Creates a new OvsUnixCtl using a Unix socket already connected to the daemon, given as a
+    /// file descriptor, e.g: passed to a sandboxed process not allowed to open the socket.
+    ///
+    /// As the socket can't be connected again, reconnecting (explicitly or not) fails.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptor must be an open and connected Unix stream socket. Its ownership is
+    /// transferred to the returned OvsUnixCtl, which closes it: it must not be used nor closed by
+    /// anything else.
+    pub unsafe fn from_raw_fd(fd: RawFd, timeout: Option<Duration>) -> Result<OvsUnixCtl> {
+        Ok(Self::with_client(
+            // SAFETY: guaranteed by the caller.
+            unsafe { jsonrpc::Client::unix_fd(fd, timeout.or(Some(Duration::from_secs(1)))) }?,
+        ))
+    }
+
+    /// Creates a new OvsUnixCtl connected over TCP, e.g: to a daemon whose unixctl server was
+    /// started with a `ptcp:` address.
+    pub fn tcp<A: ToSocketAddrs>(addr: A, timeout: Option<Duration>) -> Result<OvsUnixCtl> {
+        Ok(Self::with_client(jsonrpc::Client::tcp(
+            addr,
+            timeout.or(Some(Duration::from_secs(1))),
+        )?))
+    }
 
-        // Find $OVS_RUNDIR/{target}.{pid}.ctl
-        let sock_path = rundir.as_ref().join(format!("{}.{}.ctl", &target, pid_str));
-        if !sock_path.exists() {
-            return Err(Error::SocketNotFound(format!("{}", sock_path.display())));
+    /// Creates a new OvsUnixCtl using a custom transport, e.g: a mock replaying canned
+    /// responses in tests.
+    pub fn custom<T: CustomTransport + 'static>(transport: T) -> Result<OvsUnixCtl> {
+        Ok(Self::with_client(jsonrpc::Client::custom(Box::new(
+            transport,
+        ))?))
+    }
+
+    fn with_client(client: jsonrpc::Client<Transport>) -> OvsUnixCtl {
+        Self {
+            client,
+            target: None,
+            cache: HashMap::new(),
+            dry_run: false,
+            dry_run_reads: false,
         }
-        Ok(sock_path)
     }
 
-    fn find_socket(target: String) -> Result<PathBuf> {
-        let rundir: String = match env::var_os("OVS_RUNDIR") {
-            Some(rundir) => rundir.into_string().unwrap_or(DEFAULT_RUNDIR.to_string()),
-            None => DEFAULT_RUNDIR.to_string(),
-        };
-        Self::find_socket_at(target.as_str(), PathBuf::from(rundir))
+    /// Returns the path to the socket of the daemon, `None` if not connected through a Unix
+    /// socket in the filesystem.
+    ///
+    /// When the daemon is looked up by name, the path is the one of the current connection: it
+    /// changes if the daemon was restarted and a reconnection happened since.
+    pub fn socket_path(&self) -> Option<&Path> {
+        self.client.stream_client().path()
+    }
+
+    /// Returns the name of the daemon, e.g: `ovs-vswitchd`, `None` if it wasn't looked up by
+    /// name.
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Sets an idle timeout: if no command was issued for longer than the timeout, the connection
+    /// is closed and a new one is created before running the next command.
+    ///
+    /// This avoids holding the daemon's socket busy with a mostly idle connection.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> OvsUnixCtl {
+        self.client = self.client.idle_timeout(timeout);
+        self
+    }
+
+    /// Sets the receiver of telemetry about the calls made to the daemon, e.g: an adapter to a
+    /// metrics backend. By default, nothing is recorded.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> OvsUnixCtl {
+        self.client.metrics(metrics);
+        self
+    }
+
+    /// Enables the dry-run mode: commands are not sent to the daemon and [`Error::DryRun`] is
+    /// returned instead, which allows showing what would be run.
+    ///
+    /// If `allow_read_only` is true, commands known not to modify the daemon state (e.g:
+    /// "*/show", "dpctl/dump-flows", "version") are still sent. The detection is based on the
+    /// command name and is conservative: commands it can't tell apart are intercepted.
+    pub fn with_dry_run(mut self, allow_read_only: bool) -> OvsUnixCtl {
+        self.dry_run = true;
+        self.dry_run_reads = allow_read_only;
+        self
+    }
+
+    /// Sets a callback called each time the connection to the daemon is re-established, either
+    /// explicitly through [`OvsUnixCtl::reconnect`] or automatically, e.g: after the idle timeout.
+    ///
+    /// This is meant for observability, e.g: logging daemon restarts. The callback can't fail nor
+    /// affect the reconnection.
+    pub fn with_reconnect_callback(
+        mut self,
+        mut callback: Box<dyn FnMut(&ReconnectEvent) + Send>,
+    ) -> OvsUnixCtl {
+        self.client
+            .on_reconnect(jsonrpc::ReconnectHook(Box::new(move |old, new, error| {
+                callback(&ReconnectEvent {
+                    old_path: old.path(),
+                    new_path: new.path(),
+                    error,
+                })
+            })));
+        self
+    }
+
+    /// Enables or disables the automatic reconnection: if a command fails because the connection
+    /// to the daemon was broken (reset or broken pipe), a new connection is made and the command
+    /// is sent again, once. When the client was created from a target name, its socket is looked
+    /// up again through the pidfile, as the daemon might have been restarted with a new PID.
+    ///
+    /// Other failures, e.g: timeouts or protocol errors, are never retried as the daemon might
+    /// have run the command already. Streaming, interactive and batched calls aren't retried
+    /// either. Disabled by default.
+    pub fn with_reconnect(mut self, enabled: bool) -> OvsUnixCtl {
+        self.client.auto_reconnect(enabled);
+        self
+    }
+
+    /// Closes the connection to the daemon and opens a new one.
+    pub fn reconnect(&mut self) -> Result<()> {
+        self.client.reconnect(None)
+    }
+
+    /// Checks the daemon is alive by running the cheap "version" command, returning the
+    /// round-trip latency.
+    ///
+    /// If the connection is broken, the error is returned as is, unless the automatic
+    /// reconnection is enabled (see [`OvsUnixCtl::with_reconnect`]).
+    pub fn ping(&mut self) -> Result<Duration> {
+        let start = Instant::now();
+        self.run("version", None)?;
+        Ok(start.elapsed())
     }
 
     /// Runs the common "list-commands" command and returns the list of commands and their
     /// arguments.
     pub fn list_commands(&mut self) -> Result<Vec<(String, String)>> {
-        let response: jsonrpc::Response<String> = self.client.call("list-commands")?;
-        Ok(response
-            .result
-            .ok_or(Error::OvsInvalidResponse {
-                cmd: "list-commands".to_string(),
-                response: String::default(),
-                error: "should not be empty".to_string(),
-            })?
-            .lines()
-            .skip(1)
-            .map(|l| {
-                let (cmd, args) = l.trim().split_once(char::is_whitespace).unwrap_or((l, ""));
-                (cmd.trim().into(), args.trim().into())
+        parse::list_commands(&self.run_output("list-commands", None)?)
+    }
+
+    /// Runs the common "list-commands" command and returns the commands and their arguments
+    /// grouped by module, i.e: the part of the command name before the first '/'.
+    ///
+    /// Commands that don't belong to a module are grouped under the empty string key. Commands
+    /// are sorted within each group.
+    pub fn commands_by_module(&mut self) -> Result<BTreeMap<String, Vec<(String, String)>>> {
+        Ok(group_by_module(self.list_commands()?))
+    }
+
+    /// Returns the arguments of the given command, e.g: `[port]` for "bond/show", as listed by
+    /// the common "list-commands" command.
+    ///
+    /// Unixctl has no per-command help, the full list of commands is retrieved.
+    pub fn help(&mut self, command: &str) -> Result<String> {
+        self.list_commands()?
+            .into_iter()
+            .find_map(|(cmd, args)| (cmd == command).then_some(args))
+            .ok_or_else(|| Error::UnknownCommand {
+                cmd: command.to_string(),
             })
-            .collect())
     }
 
     /// Retrieve the version of the running daemon.
-    pub fn version(&mut self) -> Result<(u32, u32, u32, String)> {
-        let response: jsonrpc::Response<String> = self.client.call("version")?;
-        let invalid = InvalidResponse(
-            "version".to_string(),
-            response.result.clone().unwrap_or_default(),
-        );
-
-        match response
-            .result
-            .ok_or(invalid.error("should not be empty".to_string()))?
-            .trim()
-            .strip_prefix("ovs-vswitchd (Open vSwitch) ")
-            .ok_or(invalid.error("invalid prefix".to_string()))?
-            .splitn(4, &['.', '-'])
-            .collect::<Vec<&str>>()[..]
-        {
-            [x, y, z] => Ok((
-                x.to_string()
-                    .parse()
-                    .map_err(|e| invalid.error(format!("can't parse {x}: {e}")))?,
-                y.to_string()
-                    .parse()
-                    .map_err(|e| invalid.error(format!("can't parse {y}: {e}")))?,
-                z.to_string()
-                    .parse()
-                    .map_err(|e| invalid.error(format!("can't parse {z}: {e}")))?,
-                String::default(),
-            )),
-            [x, y, z, patch] => Ok((
-                x.to_string()
-                    .parse()
-                    .map_err(|e| invalid.error(format!("can't parse {x}: {e}")))?,
-                y.to_string()
-                    .parse()
-                    .map_err(|e| invalid.error(format!("can't parse {y}: {e}")))?,
-                z.to_string()
-                    .parse()
-                    .map_err(|e| invalid.error(format!("can't parse {z}: {e}")))?,
-                String::from(patch),
-            )),
-            _ => Err(invalid.error("parse error".to_string())),
-        }
+    pub fn version(&mut self) -> Result<OvsVersion> {
+        parse::version(&self.run_output("version", None)?).map(OvsVersion::from)
     }
 
     /// Run an arbitrary command.
+    ///
+    /// The result is returned exactly as sent by the daemon, including any trailing newline. See
+    /// [`OvsUnixCtl::run_trimmed`] to get rid of it.
     pub fn run(&mut self, cmd: &str, params: Option<&[&str]>) -> Result<Option<String>> {
+        self.check_command(cmd, params.unwrap_or_default())?;
         let response: jsonrpc::Response<String> = match params {
             Some(params) => self.client.call_params(cmd, params)?,
             None => self.client.call(cmd)?,
         };
         Ok(response.result)
     }
-}
 
-/// Convenient struct to make it easy to build OvsInvalidResponse errors during parsing.
-struct InvalidResponse(String, String);
-impl InvalidResponse {
-    pub(crate) fn error(&self, error: String) -> Error {
-        Error::OvsInvalidResponse {
-            cmd: self.0.clone(),
-            response: self.1.clone(),
-            error,
-        }
+    /// Sends an arbitrary method as a notification, i.e: without waiting for a reply.
+    ///
+    /// As no reply is sent back, there is no way to know whether the method failed. Note that
+    /// the unixctl servers of OVS daemons don't accept notifications and close the connection
+    /// when receiving one: this is meant for other OVS-style JSON-RPC peers.
+    pub fn notify(&mut self, method: &str, params: Option<&[&str]>) -> Result<()> {
+        self.check_command(method, params.unwrap_or_default())?;
+        self.client.notify(method, params.unwrap_or_default())
     }
-}
 
-#[cfg(test)]
-mod tests {
-
-    use std::{
-        path::{Path, PathBuf},
-        process::{id, Command, Stdio},
-    };
+    /// Sends an arbitrary command without waiting for its response and returns the id of the
+    /// request. The response can be received later using [`OvsUnixCtl::try_recv_pending`].
+    ///
+    /// Blocking calls made in the meantime discard the responses to previous requests, this one
+    /// included, while waiting for theirs.
+    pub fn send(&mut self, cmd: &str, params: Option<&[&str]>) -> Result<RpcId> {
+        self.check_command(cmd, params.unwrap_or_default())?;
+        self.client.send(cmd, params.unwrap_or_default())
+    }
 
-    use super::*;
+    /// Receives the next message, typically the response to a request made with
+    /// [`OvsUnixCtl::send`], if it was fully received already. Returns `None` without blocking
+    /// otherwise.
+    ///
+    /// This allows an event loop to drive the read side: see the [`AsRawFd`] implementation to
+    /// get the file descriptor to wait on. Parts of messages read are kept until the rest is
+    /// received. Errors returned by the daemon are left in the response.
+    pub fn try_recv_pending<R: DeserializeOwned>(&mut self) -> Result<Option<Response<R>>> {
+        self.client.try_recv_pending()
+    }
 
-    fn ovs_setup(test: &str) -> PathBuf {
-        let tmpdir = format!("/tmp/ovs-unixctl-test-{}-{}", id(), test);
-        let ovsdb_path = PathBuf::from(&tmpdir).join("conf.db");
+    /// Calls an arbitrary method and returns the full response.
+    ///
+    /// Unlike [`OvsUnixCtl::run`], an error returned by the daemon is not converted into
+    /// [`Error::Command`] but left in the response, so it can be told apart from transport
+    /// failures, which are still returned as errors.
+    pub fn call_raw<R: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: Option<&[&str]>,
+    ) -> Result<Response<R>> {
+        self.check_command(method, params.unwrap_or_default())?;
+        self.client.call_raw(method, params.unwrap_or_default())
+    }
 
-        let schema: PathBuf = match env::var_os("OVS_DATADIR") {
-            Some(datadir) => datadir
-                .into_string()
-                .expect("OVS_DATADIR has non-unicode content")
-                .into(),
-            None => "/usr/share/openvswitch/vswitch.ovsschema".into(),
-        };
+    /// Calls an arbitrary method with JSON parameters and returns its JSON result.
+    ///
+    /// Unlike [`OvsUnixCtl::run`], which is limited to the string parameters and results unixctl
+    /// commands use, this can be used against any OVS-style JSON-RPC peer.
+    pub fn call_value(
+        &mut self,
+        method: &str,
+        params: &[serde_json::Value],
+    ) -> Result<serde_json::Value> {
+        let str_params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+        self.check_command(method, &str_params)?;
+        let response: jsonrpc::Response<serde_json::Value> =
+            self.client.call_params_value(method, params)?;
+        Ok(response.result.unwrap_or_default())
+    }
 
-        fs::create_dir_all(&tmpdir).expect("cannot create tmp dir");
+    /// Sends an arbitrary JSON message and returns the next message received, bypassing the
+    /// typed [`Request`](crate::Request) and [`Response`].
+    ///
+    /// This gives full control over what is sent, e.g: to use a string id. Nothing is checked:
+    /// the message is not required to be a request and the received one is not required to be its
+    /// reply. If the message has a method, it is still subject to the dry-run mode.
+    pub fn send_recv_raw(&mut self, request_json: serde_json::Value) -> Result<serde_json::Value> {
+        if let Some(method) = request_json["method"].as_str() {
+            self.check_command::<&str>(method, &[])?;
+        }
+        self.client.send_recv_raw(request_json)
+    }
 
-        Command::new("ovsdb-tool")
-            .arg("create")
-            .arg(&ovsdb_path)
-            .arg(&schema)
-            .status()
-            .expect("Failed to create OVS database");
+    /// Calls a method whose reply is followed by a stream of messages on the same connection,
+    /// e.g: the "monitor" method of OVSDB servers, and returns them as a [`Subscription`].
+    ///
+    /// Only one subscription can be active per connection: the subscription borrows the client,
+    /// which can't be used until it is dropped. As messages might still be received afterwards,
+    /// [`OvsUnixCtl::reconnect`] should be called before running other commands.
+    pub fn subscribe(
+        &mut self,
+        method: &str,
+        params: &[serde_json::Value],
+    ) -> Result<Subscription<'_>> {
+        let str_params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+        self.check_command(method, &str_params)?;
+        Ok(Subscription(self.client.subscribe(method, params)?))
+    }
 
-        let ovsdb_logfile = Path::new(&tmpdir).join("ovsdb-server.log");
-        Command::new("ovsdb-server")
-            .env("OVS_RUNDIR", &tmpdir)
-            .arg(&ovsdb_path)
-            .arg("--detach")
-            .arg("--no-chdir")
-            .arg("--pidfile")
-            .arg(format!(
-                "--remote=punix:{}",
-                Path::new(&tmpdir).join("db.sock").to_str().unwrap()
-            ))
-            .arg(format!("--log-file={}", ovsdb_logfile.to_str().unwrap()))
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .expect("Failed to start ovsdb-server");
+    /// Subscribes as [`OvsUnixCtl::subscribe`] does, for long-lived monitoring loops: if
+    /// `reconnect` is true, losing the connection doesn't end the iteration but makes a new
+    /// subscription, see [`Monitor`].
+    pub fn monitor(
+        &mut self,
+        method: &str,
+        params: &[serde_json::Value],
+        reconnect: bool,
+    ) -> Result<Monitor<'_>> {
+        let str_params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+        self.check_command(method, &str_params)?;
+        Ok(Monitor {
+            subscription: self.client.subscribe(method, params)?,
+            reconnect,
+        })
+    }
 
-        let ovs_logfile = Path::new(&tmpdir).join("ovs-vswitchd.log");
-        Command::new("ovs-vswitchd")
-            .env("OVS_RUNDIR", &tmpdir)
-            .arg("--detach")
-            .arg("--no-chdir")
-            .arg("--pidfile")
-            .arg(format!("--log-file={}", ovs_logfile.to_str().unwrap()))
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .expect("Failed to start ovs-vswitchd");
-        std::thread::sleep(Duration::from_secs(1));
-        PathBuf::from(tmpdir)
+    /// Run an arbitrary command requesting its output in JSON format and returns it.
+    ///
+    /// The output format is selected on the connection using the "set-options" command (OVS >=
+    /// 3.3), and restored to text afterwards. Only commands supporting JSON output can be used.
+    ///
+    /// The `--pretty` option is never requested: it only adds whitespaces to the output, which
+    /// are irrelevant once parsed, and isn't supported by all versions.
+    pub fn run_json(&mut self, cmd: &str, params: Option<&[&str]>) -> Result<serde_json::Value> {
+        self.check_command(cmd, params.unwrap_or_default())?;
+        self.run("set-options", Some(&["--format", "json"]))?;
+        let response: Result<jsonrpc::Response<serde_json::Value>> = match params {
+            Some(params) => self.client.call_params(cmd, params),
+            None => self.client.call(cmd),
+        };
+        // The format is restored even if the command failed, whose error is the one reported.
+        let restored = self.run("set-options", Some(&["--format", "text"]));
+        let result = response?.result.unwrap_or_default();
+        restored?;
+        Ok(result)
     }
 
-    fn ovs_cleanup(tmpdir: &Path) {
-        // Find and kill the processes based on PID files
-        for daemon in &["ovsdb-server", "ovs-vswitchd"] {
-            let log_file = tmpdir.join(format!("{}.log", daemon));
-            if let Ok(log) = fs::read_to_string(&log_file) {
-                println!("{}.log: \n{}", daemon, log);
+    /// Run an arbitrary command, reusing its result if the same command (with the same
+    /// parameters) was run through this function less than `ttl` ago.
+    ///
+    /// This is meant to reduce the load on the daemon when polling a command rapidly. It must
+    /// only be used for read-only commands: a cached command is not run again.
+    ///
+    /// Results older than `ttl` are dropped each time a new one is stored, for the cache not to
+    /// grow when polling commands with varying parameters.
+    pub fn run_cached(
+        &mut self,
+        cmd: &str,
+        params: Option<&[&str]>,
+        ttl: Duration,
+    ) -> Result<Option<String>> {
+        let key = (
+            cmd.to_string(),
+            params
+                .unwrap_or_default()
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        );
+        if let Some((fetched, result)) = self.cache.get(&key) {
+            if fetched.elapsed() < ttl {
+                return Ok(result.clone());
             }
-            let pid_file = tmpdir.join(format!("{}.pid", daemon));
+        }
 
-            if pid_file.exists() {
-                if let Ok(pid) = fs::read_to_string(&pid_file) {
-                    if let Ok(pid) = pid.trim().parse::<i32>() {
-                        Command::new("kill")
-                            .arg("-9")
-                            .arg(pid.to_string())
-                            .status()
-                            .expect("Failed to kill daemon process");
-                    }
-                }
-            }
+        let result = self.run(cmd, params)?;
+        self.cache.retain(|_, (fetched, _)| fetched.elapsed() < ttl);
+        self.cache.insert(key, (Instant::now(), result.clone()));
+        Ok(result)
+    }
+
+    /// Run an arbitrary command that has to complete before the given deadline.
+    ///
+    /// The time remaining until the deadline is used as the timeout of the call. If the deadline
+    /// has already passed, [`Error::Timeout`] is returned without running the command.
+    pub fn run_until(
+        &mut self,
+        cmd: &str,
+        params: Option<&[&str]>,
+        deadline: Instant,
+    ) -> Result<Option<String>> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(Error::Timeout);
+        }
+        self.run_timeout(cmd, params, remaining)
+    }
+
+    /// Run an arbitrary command using the given timeout instead of the one set at construction,
+    /// e.g: for commands known to take long on a busy daemon such as "dpctl/dump-flows".
+    ///
+    /// The previous timeout is restored afterwards, even if the command fails.
+    pub fn run_timeout(
+        &mut self,
+        cmd: &str,
+        params: Option<&[&str]>,
+        timeout: Duration,
+    ) -> Result<Option<String>> {
+        self.check_command(cmd, params.unwrap_or_default())?;
+        let response: jsonrpc::Response<String> =
+            self.client.with_timeout(timeout, |client| match params {
+                Some(params) => client.call_params(cmd, params),
+                None => client.call(cmd),
+            })?;
+        Ok(response.result)
+    }
+
+    /// Run several commands in a single round-trip to the daemon.
+    ///
+    /// Commands are given as `(command, parameters)` tuples. The result of each command is
+    /// returned at the same position as the command; an error returned by the daemon for a
+    /// command is reported in its result and doesn't affect the others.
+    pub fn run_batch(
+        &mut self,
+        commands: &[(&str, &[&str])],
+    ) -> Result<Vec<Result<Option<String>>>> {
+        for (cmd, params) in commands {
+            self.check_command(cmd, params)?;
+        }
+        Ok(self
+            .client
+            .call_batch::<String, &str>(commands)?
+            .into_iter()
+            .zip(commands)
+            .map(|(response, (cmd, params))| match response.error {
+                Some(error) => Err(jsonrpc::command_error(cmd, params, error)),
+                None => Ok(response.result),
+            })
+            .collect())
+    }
+
+    /// Run an arbitrary command taking binary data as its last parameter, e.g: a packet. The data
+    /// is encoded using [`encode_binary_param`].
+    pub fn run_with_binary(
+        &mut self,
+        cmd: &str,
+        text_args: &[&str],
+        binary: &[u8],
+    ) -> Result<Option<String>> {
+        let binary = encode_binary_param(binary);
+        let params: Vec<&str> = text_args.iter().copied().chain([binary.as_str()]).collect();
+        self.run(cmd, Some(&params))
+    }
+
+    /// Run a full command line, e.g: `bond/show bond0`, as it would be given to `ovs-appctl`.
+    ///
+    /// The line is split on whitespaces: the first word is the command and the following ones its
+    /// parameters. Quoting is not supported, use [`OvsUnixCtl::run`] for parameters containing
+    /// whitespaces.
+    pub fn run_str(&mut self, line: &str) -> Result<Option<String>> {
+        let mut words = line.split_whitespace();
+        let cmd = words.next().unwrap_or_default();
+        let params: Vec<&str> = words.collect();
+        match params.is_empty() {
+            true => self.run(cmd, None),
+            false => self.run(cmd, Some(&params)),
+        }
+    }
+
+    /// Run an arbitrary command and returns its result without trailing whitespaces.
+    pub fn run_trimmed(&mut self, cmd: &str, params: Option<&[&str]>) -> Result<Option<String>> {
+        Ok(self
+            .run(cmd, params)?
+            .map(|result| result.trim_end().to_string()))
+    }
+
+    /// Run an arbitrary command whose result is split across several responses sharing the
+    /// request id, and returns the concatenation of their results. A response with an empty
+    /// result terminates the result.
+    ///
+    /// No command of the current OVS releases splits its result this way, all return a single
+    /// response and must be run using [`OvsUnixCtl::run`]. This is meant for peers (or future
+    /// commands) streaming large results; using it against a command returning a single response
+    /// blocks until the timeout.
+    pub fn call_streaming_result(
+        &mut self,
+        method: &str,
+        params: Option<&[&str]>,
+    ) -> Result<String> {
+        self.check_command(method, params.unwrap_or_default())?;
+        self.client
+            .call_streaming(method, params.unwrap_or_default())
+    }
+
+    /// Run an arbitrary command, dispatching the notifications the daemon might send before the
+    /// final response to the provided callback.
+    ///
+    /// Notifications are messages with a method and a null id. If the callback returns some value, it is sent
+    /// back to the daemon as the result of a response with a null id.
+    pub fn run_interactive<F>(
+        &mut self,
+        cmd: &str,
+        params: Option<&[&str]>,
+        on_notification: F,
+    ) -> Result<Option<String>>
+    where
+        F: FnMut(serde_json::Value) -> Option<Vec<String>>,
+    {
+        self.check_command(cmd, params.unwrap_or_default())?;
+        let response: jsonrpc::Response<String> =
+            self.client
+                .call_interactive(cmd, params.unwrap_or_default(), on_notification)?;
+        Ok(response.result)
+    }
+
+    /// Checks a command can be sent: returns [`Error::InvalidCommand`] if its name is invalid and
+    /// [`Error::DryRun`] if it must not be sent because of the dry-run mode.
+    fn check_command<P: AsRef<str>>(&self, cmd: &str, params: &[P]) -> Result<()> {
+        if cmd.is_empty() || cmd.contains(char::is_whitespace) {
+            return Err(Error::InvalidCommand {
+                cmd: cmd.to_string(),
+            });
+        }
+        if !self.dry_run || (self.dry_run_reads && is_read_only(cmd)) {
+            return Ok(());
+        }
+        Err(Error::DryRun {
+            cmd: cmd.to_string(),
+            params: params
+                .iter()
+                .map(|p| p.as_ref())
+                .collect::<Vec<&str>>()
+                .join(", "),
+        })
+    }
+
+    /// Runs a command whose output is expected to be non-empty, e.g: before parsing it.
+    pub(crate) fn run_output(&mut self, cmd: &str, params: Option<&[&str]>) -> Result<String> {
+        self.run(cmd, params)?.ok_or(Error::OvsInvalidResponse {
+            cmd: cmd.to_string(),
+            response: String::default(),
+            error: "should not be empty".to_string(),
+        })
+    }
+}
+
+/// Encodes binary data, e.g: a packet, to be passed as a command parameter.
+///
+/// OVS commands taking binary data (e.g: the packet of "ofproto/trace") expect it as a string of
+/// hexadecimal digits, two per byte, without separators.
+pub fn encode_binary_param(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Returns the directory holding the daemons pidfiles and sockets: the one specified in the
+/// OVS_RUNDIR env variable or the default one.
+fn rundir() -> PathBuf {
+    // Paths do not have to be valid UTF-8, keep OVS_RUNDIR as an OsString.
+    match env::var_os("OVS_RUNDIR") {
+        Some(rundir) => PathBuf::from(rundir),
+        None => PathBuf::from(DEFAULT_RUNDIR),
+    }
+}
+
+/// Finds the socket of the target through its pidfile in the given directory, i.e:
+/// `{rundir}/{target}.{pid}.ctl`.
+///
+/// If the process the pidfile points to isn't running, e.g: the daemon crashed, the socket of a
+/// running instance is looked for among the `{rundir}/{target}.*.ctl` ones instead.
+fn find_socket(target: &str, rundir: &Path) -> Result<PathBuf> {
+    // Find $OVS_RUNDIR/{target}.pid
+    let pidfile_path = rundir.join(format!("{}.pid", &target));
+    let pid_str = fs::read_to_string(pidfile_path.clone()).map_err(|_| Error::OvsNotRunning)?;
+    let pid_str = pid_str.trim();
+
+    if pid_str.is_empty() {
+        return Err(Error::OvsNotRunning);
+    }
+    if !pid_alive(pid_str) {
+        return find_live_socket(target, rundir);
+    }
+
+    // Find $OVS_RUNDIR/{target}.{pid}.ctl
+    let sock_path = rundir.join(format!("{}.{}.ctl", &target, pid_str));
+    if !sock_path.exists() {
+        return Err(Error::SocketNotFound(format!("{}", sock_path.display())));
+    }
+    Ok(sock_path)
+}
+
+/// Finds the socket of a running instance of the target among the `{rundir}/{target}.*.ctl` ones,
+/// picking the lowest PID if several are running.
+fn find_live_socket(target: &str, rundir: &Path) -> Result<PathBuf> {
+    let prefix = format!("{target}.");
+    let mut socks: Vec<(u32, PathBuf)> = fs::read_dir(rundir)
+        .map_err(|_| Error::OvsNotRunning)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let pid = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix(&prefix)?
+                .strip_suffix(".ctl")?
+                .to_string();
+            pid_alive(&pid).then_some((pid.parse().ok()?, path))
+        })
+        .collect();
+    socks.sort();
+    socks
+        .into_iter()
+        .next()
+        .map(|(_, path)| path)
+        .ok_or(Error::OvsNotRunning)
+}
+
+/// Returns whether the process with the given PID is running. Without procfs, e.g: on non-Linux
+/// systems, processes are always considered running.
+///
+/// Daemons running in another PID namespace aren't seen as running.
+fn pid_alive(pid: &str) -> bool {
+    if !Path::new("/proc/self").exists() {
+        return true;
+    }
+    pid.parse::<u32>()
+        .is_ok_and(|pid| Path::new("/proc").join(pid.to_string()).exists())
+}
+
+/// Returns the known daemon whose name is the closest to the given unknown target, if close enough
+/// for the target to likely be a typo.
+///
+/// Systemd-style instance names, e.g: `ovs-vswitchd@br0`, are suggested a name with the same
+/// instance; targets whose daemon name is known are not suggested anything.
+fn suggest_target(target: &str) -> Option<String> {
+    let (name, instance) = match target.split_once('@') {
+        Some((name, instance)) => (name, Some(instance)),
+        None => (target, None),
+    };
+    if KNOWN_TARGETS.contains(&name) {
+        return None;
+    }
+    KNOWN_TARGETS
+        .iter()
+        .map(|known| (levenshtein(name, known), *known))
+        .filter(|(distance, _)| *distance <= 3)
+        .min()
+        .map(|(_, known)| match instance {
+            Some(instance) => format!("{known}@{instance}"),
+            None => known.to_string(),
+        })
+}
+
+/// Computes the edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur.push(substitution.min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Tells whether a command is known not to modify the daemon state, based on its name: one of
+/// the dash-separated words of its last component must be a read verb (e.g: "show", "dump") and
+/// none of them a write verb (e.g: "set", "flush").
+fn is_read_only(cmd: &str) -> bool {
+    const READ: &[&str] = &[
+        "show", "dump", "list", "get", "lookup", "info", "status", "version", "help",
+    ];
+    const WRITE: &[&str] = &[
+        "set",
+        "add",
+        "del",
+        "mod",
+        "flush",
+        "clear",
+        "reset",
+        "enable",
+        "disable",
+        "rebalance",
+    ];
+
+    // Only changes the output format of the current connection.
+    if cmd == "set-options" {
+        return true;
+    }
+    let words: Vec<&str> = cmd
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .split('-')
+        .collect();
+    words.iter().any(|w| READ.contains(w)) && !words.iter().any(|w| WRITE.contains(w))
+}
+
+/// Lists the commands added, removed or whose arguments changed from daemon `a` to daemon `b`,
+/// e.g: to find out which commands are affected by an upgrade.
+pub fn diff_commands(a: &mut OvsUnixCtl, b: &mut OvsUnixCtl) -> Result<CommandDiff> {
+    Ok(diff_command_lists(a.list_commands()?, b.list_commands()?))
+}
+
+/// Computes the differences between two lists of commands, see [`diff_commands`].
+fn diff_command_lists(a: Vec<(String, String)>, b: Vec<(String, String)>) -> CommandDiff {
+    let a: BTreeMap<String, String> = a.into_iter().collect();
+    let mut b: BTreeMap<String, String> = b.into_iter().collect();
+    let mut diff = CommandDiff::default();
+    for (cmd, a_args) in a {
+        match b.remove(&cmd) {
+            None => diff.removed.push((cmd, a_args)),
+            Some(b_args) if b_args != a_args => diff.changed_args.push((cmd, a_args, b_args)),
+            Some(_) => (),
+        }
+    }
+    diff.added = b.into_iter().collect();
+    diff
+}
+
+/// Groups commands by the part of their name before the first '/'.
+fn group_by_module(commands: Vec<(String, String)>) -> BTreeMap<String, Vec<(String, String)>> {
+    let mut modules: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for (cmd, args) in commands {
+        let module = cmd.split_once('/').map(|(m, _)| m).unwrap_or_default();
+        modules
+            .entry(module.to_string())
+            .or_default()
+            .push((cmd, args));
+    }
+    modules.values_mut().for_each(|cmds| cmds.sort());
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        path::{Path, PathBuf},
+        process::{id, Command, Stdio},
+        sync::{Arc, Mutex},
+    };
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{testing::FakeServer, CustomStream};
+
+    fn ovs_tmpdir(test: &str) -> String {
+        format!("/tmp/ovs-unixctl-test-{}-{}", id(), test)
+    }
+
+    fn ovs_vsctl(test: &str, args: &[&str]) {
+        let status = Command::new("ovs-vsctl")
+            .arg(format!("--db=unix:{}/db.sock", ovs_tmpdir(test)))
+            .args(args)
+            .status()
+            .expect("Failed to run ovs-vsctl");
+        assert!(status.success());
+    }
+
+    fn ovs_setup(test: &str) -> PathBuf {
+        let tmpdir = ovs_tmpdir(test);
+        let ovsdb_path = PathBuf::from(&tmpdir).join("conf.db");
+
+        let schema: PathBuf = match env::var_os("OVS_DATADIR") {
+            Some(datadir) => datadir
+                .into_string()
+                .expect("OVS_DATADIR has non-unicode content")
+                .into(),
+            None => "/usr/share/openvswitch/vswitch.ovsschema".into(),
+        };
+
+        fs::create_dir_all(&tmpdir).expect("cannot create tmp dir");
+
+        Command::new("ovsdb-tool")
+            .arg("create")
+            .arg(&ovsdb_path)
+            .arg(&schema)
+            .status()
+            .expect("Failed to create OVS database");
+
+        let ovsdb_logfile = Path::new(&tmpdir).join("ovsdb-server.log");
+        Command::new("ovsdb-server")
+            .env("OVS_RUNDIR", &tmpdir)
+            .arg(&ovsdb_path)
+            .arg("--detach")
+            .arg("--no-chdir")
+            .arg("--pidfile")
+            .arg(format!(
+                "--remote=punix:{}",
+                Path::new(&tmpdir).join("db.sock").to_str().unwrap()
+            ))
+            .arg(format!("--log-file={}", ovsdb_logfile.to_str().unwrap()))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .expect("Failed to start ovsdb-server");
+
+        let ovs_logfile = Path::new(&tmpdir).join("ovs-vswitchd.log");
+        Command::new("ovs-vswitchd")
+            .env("OVS_RUNDIR", &tmpdir)
+            .arg("--detach")
+            .arg("--no-chdir")
+            .arg("--pidfile")
+            .arg(format!("--log-file={}", ovs_logfile.to_str().unwrap()))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .expect("Failed to start ovs-vswitchd");
+        std::thread::sleep(Duration::from_secs(1));
+        PathBuf::from(tmpdir)
+    }
+
+    fn ovs_cleanup(tmpdir: &Path) {
+        // Find and kill the processes based on PID files
+        for daemon in &["ovsdb-server", "ovs-vswitchd"] {
+            let log_file = tmpdir.join(format!("{}.log", daemon));
+            if let Ok(log) = fs::read_to_string(&log_file) {
+                println!("{}.log: \n{}", daemon, log);
+            }
+            let pid_file = tmpdir.join(format!("{}.pid", daemon));
+
+            if pid_file.exists() {
+                if let Ok(pid) = fs::read_to_string(&pid_file) {
+                    if let Ok(pid) = pid.trim().parse::<i32>() {
+                        Command::new("kill")
+                            .arg("-9")
+                            .arg(pid.to_string())
+                            .status()
+                            .expect("Failed to kill daemon process");
+                    }
+                }
+            }
         }
         if let Err(err) = fs::remove_dir_all(tmpdir) {
             println!("{}", err);
@@ -270,11 +1293,7 @@ mod tests {
             ovs_cleanup(&tmp_copy);
             println!("panic: {}", info);
         }));
-        let ovs = OvsUnixCtl::unix(
-            OvsUnixCtl::find_socket_at("ovs-vswitchd", &tmp).expect("Failed to find socket"),
-            None,
-        );
-        let ovs = ovs.unwrap();
+        let ovs = OvsUnixCtl::builder().rundir(tmp.clone()).build().unwrap();
 
         test(ovs);
 
@@ -297,12 +1316,68 @@ mod tests {
     #[cfg_attr(not(feature = "test_integration"), ignore)]
     fn version() {
         ovs_test("version", |mut ovs| {
-            let (x, y, z, _) = ovs.version().unwrap();
+            let (x, y, z, _) = ovs.version().unwrap().into();
             // We don't know what version is running, let's check at least it's not 0.0.0.
             assert!(x + y + z > 0);
         })
     }
 
+    #[test]
+    #[cfg_attr(not(feature = "test_integration"), ignore)]
+    fn db_version() {
+        ovs_test("db_version", |_| {
+            let mut db =
+                OvsUnixCtl::unix(format!("{}/db.sock", ovs_tmpdir("db_version")), None).unwrap();
+            let (x, y, z) = db.db_version("Open_vSwitch").unwrap();
+            assert!(x + y + z > 0);
+            assert!(matches!(
+                db.db_version("Unknown_DB"),
+                Err(Error::Command { .. })
+            ));
+        })
+    }
+
+    #[test]
+    #[cfg_attr(not(feature = "test_integration"), ignore)]
+    fn run_batch() {
+        ovs_test("run_batch", |mut ovs| {
+            let results = ovs
+                .run_batch(&[("version", &[]), ("list-commands", &[])])
+                .unwrap();
+            assert_eq!(results.len(), 2);
+            assert!(results[0]
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .starts_with("ovs-vswitchd"));
+            assert!(results[1]
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .contains("list-commands"));
+        })
+    }
+
+    #[test]
+    #[cfg_attr(not(feature = "test_integration"), ignore)]
+    fn vlog_levels() {
+        ovs_test("vlog_levels", |mut ovs| {
+            let level = |ovs: &mut OvsUnixCtl| {
+                ovs.vlog_list()
+                    .unwrap()
+                    .into_iter()
+                    .find(|m| m.name == "unixctl")
+                    .unwrap()
+                    .file
+            };
+            assert_eq!(level(&mut ovs), crate::VlogLevel::Info);
+            ovs.vlog_set("unixctl:file:dbg").unwrap();
+            assert_eq!(level(&mut ovs), crate::VlogLevel::Dbg);
+        })
+    }
+
     #[test]
     #[cfg_attr(not(feature = "test_integration"), ignore)]
     fn vlog() {
@@ -330,4 +1405,1299 @@ mod tests {
             assert_eq!(get_vlog_level(vlog, "unixctl"), "DBG");
         })
     }
+
+    #[test]
+    #[cfg_attr(not(feature = "test_integration"), ignore)]
+    fn bond_list() {
+        ovs_test("bond_list", |mut ovs| {
+            #[rustfmt::skip]
+            ovs_vsctl("bond_list", &[
+                "add-br", "br0",
+                "--", "set", "bridge", "br0", "datapath_type=netdev",
+                "--", "add-bond", "br0", "bond0", "p1", "p2",
+                "--", "set", "interface", "p1", "type=internal",
+                "--", "set", "interface", "p2", "type=internal",
+                "--", "set", "port", "bond0", "bond_mode=active-backup",
+            ]);
+
+            let bonds = ovs.bond_list().unwrap();
+            assert_eq!(bonds.len(), 1);
+            assert_eq!(bonds[0].name, "bond0");
+            assert_eq!(bonds[0].bond_type, "active-backup");
+            let mut slaves = bonds[0].slaves.clone();
+            slaves.sort();
+            assert_eq!(slaves, vec!["p1".to_string(), "p2".to_string()]);
+        })
+    }
+
+    #[test]
+    #[cfg_attr(not(feature = "test_integration"), ignore)]
+    fn dpif_show() {
+        ovs_test("dpif_show", |mut ovs| {
+            #[rustfmt::skip]
+            ovs_vsctl("dpif_show", &[
+                "add-br", "br0",
+                "--", "set", "bridge", "br0", "datapath_type=netdev",
+                "--", "add-port", "br0", "p1",
+                "--", "set", "interface", "p1", "type=internal", "ofport_request=5",
+            ]);
+
+            let bridges = ovs.dpif_show().unwrap();
+            assert_eq!(bridges.len(), 1);
+            assert_eq!(bridges[0].datapath_type, "netdev");
+            assert_eq!(bridges[0].bridge, "br0");
+            let p1 = bridges[0].ports.iter().find(|p| p.name == "p1").unwrap();
+            assert_eq!(p1.port_no, 5);
+            assert!(bridges[0]
+                .ports
+                .iter()
+                .any(|p| p.name == "br0" && p.port_no == 65534));
+        })
+    }
+
+    #[test]
+    fn run_interactive() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let server = FakeServer::raw("run_interactive", move |msg| {
+            if msg["method"] == "long/cmd" {
+                vec![
+                    json!({"method": "progress", "params": ["50%"], "id": null}),
+                    json!({"result": "done", "error": null, "id": msg["id"]}),
+                ]
+            } else {
+                tx.send(msg.clone()).unwrap();
+                vec![]
+            }
+        });
+
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let mut notifications = Vec::new();
+        let result = ovs
+            .run_interactive("long/cmd", Some(&["arg"]), |notif| {
+                notifications.push(notif);
+                Some(vec!["ack".to_string()])
+            })
+            .unwrap();
+        assert_eq!(result.as_deref(), Some("done"));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0]["method"], "progress");
+        assert_eq!(notifications[0]["params"], json!(["50%"]));
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![("long/cmd".to_string(), vec!["arg".to_string()])]
+        );
+        let reply = rx.recv().unwrap();
+        assert_eq!(reply["result"], json!(["ack"]));
+        assert!(reply["id"].is_null());
+    }
+
+    #[test]
+    fn run_interactive_null_id() {
+        let server = FakeServer::raw("run_interactive_null_id", |msg| {
+            match msg["method"].as_str() {
+                Some("bad/cmd") => {
+                    vec![json!({"result": null, "error": "bad request", "id": null})]
+                }
+                _ => vec![json!({"result": "done", "error": null, "id": null})],
+            }
+        });
+
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let mut notifications = 0;
+        // Errors with a null id are the ones of the request, not notifications.
+        assert!(matches!(
+            ovs.run_interactive("bad/cmd", None, |_| {
+                notifications += 1;
+                None
+            }),
+            Err(Error::Command { cmd, error, .. }) if cmd == "bad/cmd" && error == "bad request"
+        ));
+        assert!(matches!(
+            ovs.run_interactive("other/cmd", None, |_| {
+                notifications += 1;
+                None
+            }),
+            Err(Error::Protocol(_))
+        ));
+        assert_eq!(notifications, 0);
+    }
+
+    #[test]
+    fn idle_timeout() {
+        let server = FakeServer::new("idle_timeout", |_, _| Ok(String::default()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None)
+            .unwrap()
+            .with_idle_timeout(Duration::from_millis(50));
+        ovs.run("first", None).unwrap();
+        ovs.run("second", None).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        ovs.run("third", None).unwrap();
+        drop(ovs);
+
+        assert_eq!(
+            server.requests_per_connection(),
+            vec![
+                vec![
+                    ("first".to_string(), vec![]),
+                    ("second".to_string(), vec![])
+                ],
+                vec![("third".to_string(), vec![])],
+            ]
+        );
+    }
+
+    #[test]
+    fn reconnect_callback() {
+        let server = FakeServer::new("reconnect_callback", |_, _| Ok(String::default()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let cb_events = events.clone();
+        let mut ovs = OvsUnixCtl::unix(server.path(), None)
+            .unwrap()
+            .with_idle_timeout(Duration::from_millis(50))
+            .with_reconnect_callback(Box::new(move |event| {
+                cb_events.lock().unwrap().push((
+                    event.old_path.map(Path::to_path_buf),
+                    event.new_path.map(Path::to_path_buf),
+                    event.error.is_some(),
+                ))
+            }));
+        ovs.run("first", None).unwrap();
+        assert!(events.lock().unwrap().is_empty());
+
+        ovs.reconnect().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        ovs.run("second", None).unwrap();
+        drop(ovs);
+
+        let path = Some(server.path().to_path_buf());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (path.clone(), path.clone(), false),
+                (path.clone(), path, false)
+            ]
+        );
+        assert_eq!(server.requests_per_connection().len(), 3);
+    }
+
+    #[test]
+    fn auto_reconnect() {
+        use std::os::unix::net::UnixListener;
+
+        let target = format!("ovs-unixctl-fake-{}-auto_reconnect", id());
+        let rundir = env::temp_dir();
+        let pidfile = rundir.join(format!("{target}.pid"));
+
+        // First instance of the daemon, closing the connection after a single request. Both
+        // instances need the PID of a running process, let's use ours and our parent's.
+        let old_path = rundir.join(format!("{target}.{}.ctl", id()));
+        let _ = fs::remove_file(&old_path);
+        let listener = UnixListener::bind(&old_path).unwrap();
+        let old_daemon = std::thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let request: serde_json::Value = serde_json::Deserializer::from_reader(&sock)
+                .into_iter()
+                .next()
+                .unwrap()
+                .unwrap();
+            let reply = json!({"result": "", "error": null, "id": request["id"]});
+            serde_json::to_writer(&mut sock, &reply).unwrap();
+        });
+        fs::write(&pidfile, format!("{}\n", id())).unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let cb_events = events.clone();
+        let mut ovs = OvsUnixCtl::builder()
+            .target(&target)
+            .rundir(rundir)
+            .build()
+            .unwrap()
+            .with_reconnect(true)
+            .with_reconnect_callback(Box::new(move |event| {
+                cb_events.lock().unwrap().push((
+                    event.old_path.map(Path::to_path_buf),
+                    event.new_path.map(Path::to_path_buf),
+                    event.error.map(Error::code),
+                ))
+            }));
+        ovs.run("first", None).unwrap();
+        old_daemon.join().unwrap();
+
+        // The daemon restarts with a new PID.
+        let ppid = std::os::unix::process::parent_id();
+        let server = FakeServer::new(&format!("auto_reconnect.{ppid}"), |_, _| {
+            Ok("restarted".to_string())
+        });
+        fs::write(&pidfile, format!("{ppid}\n")).unwrap();
+
+        let res = ovs.run("second", None);
+        drop(ovs);
+        fs::remove_file(&old_path).unwrap();
+        fs::remove_file(&pidfile).unwrap();
+
+        assert_eq!(res.unwrap().as_deref(), Some("restarted"));
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![(
+                Some(old_path),
+                Some(server.path().to_path_buf()),
+                Some("socket")
+            )]
+        );
+        assert_eq!(server.requests(), vec![("second".to_string(), vec![])]);
+    }
+
+    #[test]
+    fn builder() {
+        let target = format!("ovs-unixctl-fake-{}-builder", id());
+        let rundir = env::temp_dir();
+        let pidfile = rundir.join(format!("{target}.pid"));
+        let server = FakeServer::new(&format!("builder.{}", id()), |_, _| Ok("found".to_string()));
+        fs::write(&pidfile, format!("{}\n", id())).unwrap();
+
+        let mut ovs = OvsUnixCtl::builder()
+            .target(&target)
+            .rundir(rundir.clone())
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        assert_eq!(ovs.run("cmd", None).unwrap().as_deref(), Some("found"));
+        assert_eq!(ovs.target(), Some(target.as_str()));
+        assert_eq!(ovs.socket_path(), Some(server.path()));
+        drop(ovs);
+
+        let mut ovs = OvsUnixCtl::with_target_in(target.clone(), &rundir, None).unwrap();
+        assert_eq!(ovs.run("cmd", None).unwrap().as_deref(), Some("found"));
+        drop(ovs);
+        fs::remove_file(&pidfile).unwrap();
+
+        let mut ovs = OvsUnixCtl::builder()
+            .target("ignored")
+            .socket_path(server.path().to_path_buf())
+            .build()
+            .unwrap();
+        assert_eq!(ovs.run("cmd", None).unwrap().as_deref(), Some("found"));
+        assert_eq!(ovs.target(), None);
+        assert_eq!(ovs.socket_path(), Some(server.path()));
+        drop(ovs);
+
+        let mut ovs = OvsUnixCtl::builder()
+            .socket_path(server.path().to_path_buf())
+            .dry_run(true)
+            .reconnect(true)
+            .idle_timeout(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        assert_eq!(ovs.run("version", None).unwrap().as_deref(), Some("found"));
+        assert!(matches!(
+            ovs.run("vlog/set", Some(&["dbg"])),
+            Err(Error::DryRun { .. })
+        ));
+
+        assert!(matches!(
+            OvsUnixCtl::builder().target(&target).rundir(rundir).build(),
+            Err(Error::OvsNotRunning)
+        ));
+        assert!(matches!(
+            OvsUnixCtl::builder()
+                .target("ovs-vswtichd")
+                .rundir(env::temp_dir())
+                .build(),
+            Err(Error::UnknownTarget { .. })
+        ));
+    }
+
+    #[test]
+    fn discover() {
+        let rundir = env::temp_dir().join(format!("ovs-unixctl-discover-{}", id()));
+        fs::create_dir_all(&rundir).unwrap();
+        let (pid, ppid) = (id(), std::os::unix::process::parent_id());
+        fs::write(rundir.join("ovsdb-server.pid"), format!("{ppid}\n")).unwrap();
+        fs::write(rundir.join(format!("ovsdb-server.{ppid}.ctl")), "").unwrap();
+        fs::write(rundir.join("ovs-vswitchd.pid"), format!("{pid}\n")).unwrap();
+        fs::write(rundir.join(format!("ovs-vswitchd.{pid}.ctl")), "").unwrap();
+        // Stale pidfile, left by a daemon which was killed.
+        fs::write(rundir.join("ovn-northd.pid"), "7\n").unwrap();
+        fs::write(rundir.join("conf.db"), "").unwrap();
+
+        let daemons = OvsUnixCtl::discover(&rundir);
+        fs::remove_dir_all(&rundir).unwrap();
+
+        assert_eq!(
+            daemons.unwrap(),
+            vec![
+                (
+                    "ovs-vswitchd".to_string(),
+                    rundir.join(format!("ovs-vswitchd.{pid}.ctl"))
+                ),
+                (
+                    "ovsdb-server".to_string(),
+                    rundir.join(format!("ovsdb-server.{ppid}.ctl"))
+                ),
+            ]
+        );
+        assert!(OvsUnixCtl::discover(&rundir).is_err());
+    }
+
+    #[test]
+    fn command_diff() {
+        let commands = |cmds: &[(&str, &str)]| {
+            cmds.iter()
+                .map(|(cmd, args)| (cmd.to_string(), args.to_string()))
+                .collect()
+        };
+        let diff = diff_command_lists(
+            commands(&[
+                ("version", ""),
+                ("dpif/show", ""),
+                ("bond/show", "[port]"),
+                ("dpif-netdev/pmd-rxq-show", "[-pmd core] [dp]"),
+            ]),
+            commands(&[
+                ("version", ""),
+                ("dpif/show", ""),
+                ("dpif-netdev/pmd-rxq-show", "[-pmd core] [-secs secs] [dp]"),
+                ("dpif-netdev/pmd-sleep-show", "[dp]"),
+                ("bond/list", ""),
+            ]),
+        );
+        assert_eq!(
+            diff,
+            CommandDiff {
+                added: commands(&[("bond/list", ""), ("dpif-netdev/pmd-sleep-show", "[dp]")]),
+                removed: commands(&[("bond/show", "[port]")]),
+                changed_args: vec![(
+                    "dpif-netdev/pmd-rxq-show".to_string(),
+                    "[-pmd core] [dp]".to_string(),
+                    "[-pmd core] [-secs secs] [dp]".to_string()
+                )],
+            }
+        );
+    }
+
+    #[test]
+    fn ovs_version() {
+        let version = OvsVersion::from((3, 3, 0, "47.el9fdp".to_string()));
+        assert_eq!(version.to_string(), "3.3.0-47.el9fdp");
+        assert_eq!(OvsVersion::new(3, 1, 0).to_string(), "3.1.0");
+        assert_eq!(version, OvsVersion::new(3, 3, 0));
+        assert!(version >= OvsVersion::new(3, 1, 0));
+        assert!(version < OvsVersion::new(3, 3, 1));
+        assert!(OvsVersion::new(2, 17, 9) < OvsVersion::new(3, 0, 0));
+        assert_eq!(
+            <(u32, u32, u32, String)>::from(version),
+            (3, 3, 0, "47.el9fdp".to_string())
+        );
+    }
+
+    #[test]
+    fn notify() {
+        let server = FakeServer::raw("notify", |msg| match msg["id"] {
+            serde_json::Value::Null => vec![],
+            _ => vec![json!({"result": "reply", "error": null, "id": msg["id"]})],
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        ovs.notify("event", Some(&["arg"])).unwrap();
+        assert_eq!(ovs.run("cmd", None).unwrap().as_deref(), Some("reply"));
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![
+                ("event".to_string(), vec!["arg".to_string()]),
+                ("cmd".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn stale_response() {
+        let server = FakeServer::raw("stale_response", |msg| {
+            let id = msg["id"].as_u64().unwrap();
+            match msg["method"].as_str() {
+                // A late response, of another type, to a previous request.
+                Some("stale") => vec![
+                    json!({"result": {"late": true}, "error": null, "id": id - 1}),
+                    json!({"result": "ok", "error": null, "id": id}),
+                ],
+                Some("stale/stream") => vec![
+                    json!({"result": {"late": true}, "error": null, "id": id - 1}),
+                    json!({"result": "ok", "error": null, "id": id}),
+                    json!({"result": "", "error": null, "id": id}),
+                ],
+                _ => vec![json!({"result": "ok", "error": null, "id": id + 1})],
+            }
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(ovs.run("stale", None).unwrap().as_deref(), Some("ok"));
+        assert_eq!(
+            ovs.call_streaming_result("stale/stream", None).unwrap(),
+            "ok"
+        );
+        assert_eq!(
+            ovs.run_interactive("stale", None, |_| None)
+                .unwrap()
+                .as_deref(),
+            Some("ok")
+        );
+        let results = ovs.run_batch(&[("stale", &[])]).unwrap();
+        assert_eq!(results[0].as_ref().unwrap().as_deref(), Some("ok"));
+        // Responses to later requests can't be explained, they are still reported.
+        assert!(matches!(ovs.run("future", None), Err(Error::Protocol(_))));
+    }
+
+    #[test]
+    fn send_recv_raw() {
+        let server = FakeServer::raw("send_recv_raw", |msg| {
+            vec![json!({"result": msg["params"][0], "error": null, "id": msg["id"]})]
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(
+            ovs.send_recv_raw(json!({"id": "custom", "method": "echo", "params": ["hi"]}))
+                .unwrap(),
+            json!({"result": "hi", "error": null, "id": "custom"})
+        );
+        drop(ovs);
+
+        let mut ovs = OvsUnixCtl::unix(server.path(), None)
+            .unwrap()
+            .with_dry_run(false);
+        assert!(matches!(
+            ovs.send_recv_raw(json!({"id": 1, "method": "echo", "params": []})),
+            Err(Error::DryRun { .. })
+        ));
+    }
+
+    #[test]
+    fn subscribe() {
+        let server = FakeServer::raw("subscribe", |msg| match msg["method"].as_str() {
+            Some("monitor") => vec![
+                json!({"result": {"Bridge": {}}, "error": null, "id": msg["id"]}),
+                json!({"method": "update", "params": [null, {"Bridge": {"br0": {}}}], "id": null}),
+                json!({"method": "update", "params": [null, {"Bridge": {"br1": {}}}], "id": null}),
+            ],
+            _ => vec![json!({"result": null, "error": "unknown", "id": msg["id"]})],
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), Some(Duration::from_millis(100))).unwrap();
+
+        let mut updates = ovs
+            .subscribe("monitor", &[json!("Open_vSwitch"), json!(null)])
+            .unwrap();
+        assert_eq!(updates.next().unwrap().unwrap(), json!({"Bridge": {}}));
+        for bridge in ["br0", "br1"] {
+            let update = updates.next().unwrap().unwrap();
+            assert_eq!(update["method"], "update");
+            assert_eq!(update["params"][1]["Bridge"][bridge], json!({}));
+        }
+        // Nothing more is sent, which doesn't end the subscription.
+        assert!(matches!(updates.next(), Some(Err(Error::Timeout))));
+        drop(updates);
+
+        ovs.reconnect().unwrap();
+        let mut updates = ovs.subscribe("other", &[]).unwrap();
+        assert!(matches!(
+            updates.next(),
+            Some(Err(Error::Command { error, .. })) if error == "unknown"
+        ));
+        assert!(updates.next().is_none());
+    }
+
+    #[test]
+    fn monitor() {
+        use std::{io::Write, os::unix::net::UnixListener};
+
+        let path = env::temp_dir().join(format!("ovs-unixctl-monitor-{}.ctl", id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let daemon = std::thread::spawn(move || {
+            for (n, last) in [(0, &b"{\"method\": \"upd"[..]), (1, &b""[..])] {
+                let (mut sock, _) = listener.accept().unwrap();
+                let request: serde_json::Value = serde_json::Deserializer::from_reader(&sock)
+                    .into_iter()
+                    .next()
+                    .unwrap()
+                    .unwrap();
+                let reply = json!({"result": n, "error": null, "id": request["id"]});
+                let update = json!({"method": "update", "params": [n], "id": null});
+                serde_json::to_writer(&mut sock, &reply).unwrap();
+                serde_json::to_writer(&mut sock, &update).unwrap();
+                // The first connection is closed in the middle of a message, the second one is
+                // kept open until the client is done.
+                sock.write_all(last).unwrap();
+                if n == 1 {
+                    let _ = std::io::Read::read(&mut sock, &mut [0]);
+                }
+            }
+        });
+
+        let mut ovs = OvsUnixCtl::unix(&path, Some(Duration::from_millis(100))).unwrap();
+        let events: Vec<Result<MonitorEvent>> = ovs
+            .monitor("monitor", &[json!("Open_vSwitch")], true)
+            .unwrap()
+            .take(6)
+            .collect();
+        drop(ovs);
+        daemon.join().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let update = |n| json!({"method": "update", "params": [n], "id": null});
+        assert_eq!(
+            events,
+            vec![
+                Ok(MonitorEvent::Message(json!(0))),
+                Ok(MonitorEvent::Message(update(0))),
+                Ok(MonitorEvent::Reconnected),
+                Ok(MonitorEvent::Message(json!(1))),
+                Ok(MonitorEvent::Message(update(1))),
+                Err(Error::Timeout),
+            ]
+        );
+    }
+
+    #[test]
+    fn instance_target() {
+        let target = format!("ovs-unixctl-fake-{}-instance@br0", id());
+        let rundir = env::temp_dir();
+        let pidfile = rundir.join(format!("{target}.pid"));
+        let server = FakeServer::new(&format!("instance@br0.{}", id()), |_, _| {
+            Ok("br0".to_string())
+        });
+        fs::write(&pidfile, format!("{}\n", id())).unwrap();
+
+        let ovs = OvsUnixCtl::with_target_in(target.clone(), &rundir, None);
+        let found = OvsUnixCtl::discover(&rundir);
+        fs::remove_file(&pidfile).unwrap();
+
+        assert_eq!(
+            ovs.unwrap().run("cmd", None).unwrap().as_deref(),
+            Some("br0")
+        );
+        assert!(found
+            .unwrap()
+            .contains(&(target, server.path().to_path_buf())));
+        assert!(matches!(
+            OvsUnixCtl::with_target_in("ovs-vswitchd@br0".into(), &rundir, None),
+            Err(Error::OvsNotRunning)
+        ));
+    }
+
+    #[test]
+    fn unknown_command() {
+        let server = FakeServer::new("unknown_command", |cmd, _| {
+            match cmd {
+            "version" => Ok("ovs-vswitchd (Open vSwitch) 3.1.2\n".to_string()),
+            "fail" => Err("failed".to_string()),
+            _ => Err(format!(
+                "\"{cmd}\" is not a valid command (use \"list-commands\" to see a list of valid commands)"
+            )),
+        }
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert!(matches!(
+            ovs.run("dpif-netdev/pmd-sleep-show", None),
+            Err(Error::UnknownCommand { cmd }) if cmd == "dpif-netdev/pmd-sleep-show"
+        ));
+        assert!(matches!(
+            ovs.run("fail", Some(&["arg"])),
+            Err(Error::Command { .. })
+        ));
+        assert!(ovs.run("version", None).is_ok());
+    }
+
+    #[test]
+    fn target_suggestion() {
+        assert_eq!(levenshtein("ovs-vswtichd", "ovs-vswitchd"), 2);
+        assert_eq!(
+            suggest_target("ovs-vswtichd").as_deref(),
+            Some("ovs-vswitchd")
+        );
+        assert_eq!(suggest_target("ovn-nortd").as_deref(), Some("ovn-northd"));
+        assert_eq!(suggest_target("ovs-vswitchd"), None);
+        assert_eq!(suggest_target("my-own-daemon"), None);
+        assert_eq!(suggest_target("ovs-vswitchd@br0"), None);
+        assert_eq!(
+            suggest_target("ovs-vswtichd@br0").as_deref(),
+            Some("ovs-vswitchd@br0")
+        );
+
+        let err = OvsUnixCtl::with_target("ovs-vswtichd".to_string(), None).unwrap_err();
+        assert!(matches!(err, Error::UnknownTarget { .. }));
+        assert!(err.to_string().contains("did you mean ovs-vswitchd?"));
+
+        // A target found but failing to connect is reported as is, even if its name is close.
+        let rundir = env::temp_dir().join(format!("ovs-unixctl-suggestion-{}", id()));
+        fs::create_dir_all(&rundir).unwrap();
+        fs::write(rundir.join("ovs-vswtichd.pid"), format!("{}\n", id())).unwrap();
+        fs::write(rundir.join(format!("ovs-vswtichd.{}.ctl", id())), "").unwrap();
+        let err =
+            OvsUnixCtl::with_target_in("ovs-vswtichd".to_string(), &rundir, None).unwrap_err();
+        fs::remove_dir_all(&rundir).unwrap();
+        assert!(matches!(err, Error::Socket(_) | Error::SocketIo { .. }));
+    }
+
+    #[test]
+    fn invalid_command() {
+        let server = FakeServer::new("invalid_command", |_, _| Ok(String::default()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let err = ovs.run("bond/show bond0", None).unwrap_err();
+        assert!(matches!(&err, Error::InvalidCommand { cmd } if cmd == "bond/show bond0"));
+        assert!(err.to_string().contains("use run_str"));
+        assert!(matches!(
+            ovs.run("", None),
+            Err(Error::InvalidCommand { .. })
+        ));
+
+        ovs.run_str("  bond/show   bond0 ").unwrap();
+        ovs.run_str("bond/list").unwrap();
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![
+                ("bond/show".to_string(), vec!["bond0".to_string()]),
+                ("bond/list".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn call_raw() {
+        let server = FakeServer::new("call_raw", |method, _| match method {
+            "version" => Ok("3.3.0".to_string()),
+            _ => Err("unknown command".to_string()),
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+
+        let response: Response = ovs.call_raw("version", None).unwrap();
+        assert_eq!(response.result.unwrap(), "3.3.0");
+        assert!(response.error.is_none());
+        assert_ne!(response.id, crate::RpcId::Null);
+
+        let response: Response = ovs.call_raw("foo", Some(&["bar"])).unwrap();
+        assert!(response.result.is_none());
+        assert_eq!(response.error.unwrap(), "unknown command");
+    }
+
+    #[test]
+    fn streaming_result() {
+        let server = FakeServer::raw("streaming_result", |msg| {
+            ["first,", "second,", "third", ""]
+                .iter()
+                .map(|chunk| json!({"result": chunk, "error": null, "id": msg["id"]}))
+                .collect()
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(
+            ovs.call_streaming_result("dump/all", Some(&["-m"]))
+                .unwrap(),
+            "first,second,third"
+        );
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![("dump/all".to_string(), vec!["-m".to_string()])]
+        );
+    }
+
+    #[test]
+    fn metrics() {
+        #[derive(Default)]
+        struct Recorder {
+            calls: Mutex<Vec<(String, Duration, bool)>>,
+            bytes: Mutex<Vec<(u64, u64)>>,
+        }
+        impl Metrics for Recorder {
+            fn record_call(&self, method: &str, latency: Duration, success: bool) {
+                self.calls
+                    .lock()
+                    .unwrap()
+                    .push((method.to_string(), latency, success));
+            }
+            fn record_bytes(&self, sent: u64, recv: u64) {
+                self.bytes.lock().unwrap().push((sent, recv));
+            }
+        }
+
+        let server = FakeServer::new("metrics", |_, _| Ok("3.3.0".to_string()));
+        let recorder = Arc::new(Recorder::default());
+        let mut ovs = OvsUnixCtl::unix(server.path(), None)
+            .unwrap()
+            .with_metrics(recorder.clone());
+        ovs.run("version", None).unwrap();
+        drop(ovs);
+
+        let calls = recorder.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        let (method, latency, success) = &calls[0];
+        assert_eq!(method, "version");
+        assert!(!latency.is_zero());
+        assert!(success);
+
+        let bytes = recorder.bytes.lock().unwrap();
+        assert_eq!(bytes.len(), 1);
+        assert!(bytes[0].0 > 0 && bytes[0].1 > 0);
+    }
+
+    #[test]
+    fn read_only_commands() {
+        for cmd in [
+            "version",
+            "list-commands",
+            "dpctl/dump-flows",
+            "dpif-netdev/pmd-rxq-show",
+            "dpif-netdev/subtable-lookup-info-get",
+            "inc-engine/show-stats",
+            "ovs/route/lookup",
+            "vlog/list",
+        ] {
+            assert!(is_read_only(cmd), "{cmd}");
+        }
+        for cmd in [
+            "recompute",
+            "vlog/set",
+            "lflow-cache/flush",
+            "dpctl/ct-set-maxconns",
+            "dpif-netdev/pmd-stats-clear",
+            "dpctl/del-flows",
+            "exit",
+        ] {
+            assert!(!is_read_only(cmd), "{cmd}");
+        }
+    }
+
+    #[test]
+    fn dry_run() {
+        let server = FakeServer::new("dry_run", |_, _| Ok("3.3.0".to_string()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None)
+            .unwrap()
+            .with_dry_run(true);
+        match ovs.run("vlog/set", Some(&["dbg"])) {
+            Err(Error::DryRun { cmd, params }) => {
+                assert_eq!(cmd, "vlog/set");
+                assert_eq!(params, "dbg");
+            }
+            res => panic!("unexpected result {res:?}"),
+        }
+        assert!(matches!(ovs.ovn_recompute(), Err(Error::DryRun { .. })));
+        assert_eq!(ovs.run("version", None).unwrap().unwrap(), "3.3.0");
+        drop(ovs);
+
+        let mut ovs = OvsUnixCtl::unix(server.path(), None)
+            .unwrap()
+            .with_dry_run(false);
+        assert!(matches!(
+            ovs.run("version", None),
+            Err(Error::DryRun { .. })
+        ));
+        drop(ovs);
+
+        assert_eq!(server.requests(), vec![("version".to_string(), vec![])]);
+    }
+
+    #[test]
+    fn from_raw_fd() {
+        use std::os::unix::{io::IntoRawFd, net::UnixStream};
+
+        let (client, server) = UnixStream::pair().unwrap();
+        let daemon = std::thread::spawn(move || {
+            let request: serde_json::Value = serde_json::Deserializer::from_reader(&server)
+                .into_iter()
+                .next()
+                .unwrap()
+                .unwrap();
+            let reply = json!({"result": "pong", "error": null, "id": request["id"]});
+            serde_json::to_writer(&server, &reply).unwrap();
+        });
+
+        let fd = client.into_raw_fd();
+        // SAFETY: the file descriptor is an open Unix socket we don't use anymore.
+        let mut ovs = unsafe { OvsUnixCtl::from_raw_fd(fd, None) }.unwrap();
+        assert_eq!(ovs.run("ping", None).unwrap().as_deref(), Some("pong"));
+        daemon.join().unwrap();
+
+        assert_eq!(ovs.socket_path(), None);
+        assert!(matches!(
+            ovs.reconnect(),
+            Err(Error::SocketIo { peer, source })
+                if peer == format!("unix-fd://{fd}")
+                    && source.kind() == std::io::ErrorKind::NotConnected
+        ));
+    }
+
+    #[test]
+    fn try_recv_pending() {
+        use std::{
+            io::Write,
+            os::unix::{io::IntoRawFd, net::UnixStream},
+        };
+
+        let (client, mut server) = UnixStream::pair().unwrap();
+        let fd = client.into_raw_fd();
+        // SAFETY: the file descriptor is an open Unix socket we don't use anymore.
+        let mut ovs = unsafe { OvsUnixCtl::from_raw_fd(fd, None) }.unwrap();
+        assert_eq!(ovs.as_raw_fd(), fd);
+
+        let id = ovs.send("version", None).unwrap();
+        assert!(ovs.try_recv_pending::<String>().unwrap().is_none());
+
+        // The response is only returned once fully written.
+        let reply = json!({"result": "3.3.0", "error": null, "id": id}).to_string();
+        let (first, second) = reply.split_at(10);
+        server.write_all(first.as_bytes()).unwrap();
+        assert!(ovs.try_recv_pending::<String>().unwrap().is_none());
+        server.write_all(second.as_bytes()).unwrap();
+        let response = ovs.try_recv_pending::<String>().unwrap().unwrap();
+        assert_eq!(response.id, id);
+        assert_eq!(response.result.as_deref(), Some("3.3.0"));
+        assert!(ovs.try_recv_pending::<String>().unwrap().is_none());
+
+        // Partially received messages are kept for blocking calls too.
+        let id = ovs.send("version", None).unwrap();
+        let reply = json!({"result": "3.3.1", "error": null, "id": id}).to_string();
+        let (first, second) = reply.split_at(10);
+        server.write_all(first.as_bytes()).unwrap();
+        assert!(ovs.try_recv_pending::<String>().unwrap().is_none());
+        server.write_all(second.as_bytes()).unwrap();
+        let request = json!({"result": "ok", "error": null, "id": 3}).to_string();
+        server.write_all(request.as_bytes()).unwrap();
+        assert_eq!(ovs.run("cmd", None).unwrap().as_deref(), Some("ok"));
+
+        drop(server);
+        assert!(ovs.try_recv_pending::<String>().is_err());
+    }
+
+    #[test]
+    fn help() {
+        let server = FakeServer::new("help", |_, _| {
+            Ok(
+                "The available commands are:\n  bond/list\n  bond/show                [port]\n"
+                    .into(),
+            )
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(ovs.help("bond/show").unwrap(), "[port]");
+        assert_eq!(ovs.help("bond/list").unwrap(), "");
+        assert!(matches!(
+            ovs.help("bond/set"),
+            Err(Error::UnknownCommand { cmd }) if cmd == "bond/set"
+        ));
+    }
+
+    #[test]
+    fn commands_by_module() {
+        let modules = group_by_module(
+            [
+                ("vlog/set", "{spec | PATTERN:destination:pattern}"),
+                ("version", ""),
+                ("bond/show", "[port]"),
+                ("list-commands", ""),
+                ("vlog/list", ""),
+                ("bond/list", ""),
+            ]
+            .iter()
+            .map(|(cmd, args)| (cmd.to_string(), args.to_string()))
+            .collect(),
+        );
+
+        assert_eq!(
+            modules.keys().collect::<Vec<&String>>(),
+            vec!["", "bond", "vlog"]
+        );
+        assert_eq!(
+            modules[""],
+            vec![
+                ("list-commands".to_string(), String::default()),
+                ("version".to_string(), String::default()),
+            ]
+        );
+        assert_eq!(
+            modules["bond"],
+            vec![
+                ("bond/list".to_string(), String::default()),
+                ("bond/show".to_string(), "[port]".to_string()),
+            ]
+        );
+        assert_eq!(
+            modules["vlog"],
+            vec![
+                ("vlog/list".to_string(), String::default()),
+                (
+                    "vlog/set".to_string(),
+                    "{spec | PATTERN:destination:pattern}".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_socket_non_utf8_rundir() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let mut name = format!("ovs-unixctl-rundir-{}-", id()).into_bytes();
+        name.push(0xff);
+        let rundir = env::temp_dir().join(OsStr::from_bytes(&name));
+        assert!(rundir.to_str().is_none());
+        fs::create_dir_all(&rundir).unwrap();
+        let sock_path = rundir.join(format!("ovs-vswitchd.{}.ctl", id()));
+        fs::write(rundir.join("ovs-vswitchd.pid"), format!("{}\n", id())).unwrap();
+        fs::write(&sock_path, "").unwrap();
+
+        let sock = find_socket("ovs-vswitchd", &rundir);
+        fs::remove_dir_all(&rundir).unwrap();
+
+        assert_eq!(sock.unwrap(), sock_path);
+    }
+
+    #[test]
+    fn find_socket_stale_pidfile() {
+        let rundir = env::temp_dir().join(format!("ovs-unixctl-stale-{}", id()));
+        fs::create_dir_all(&rundir).unwrap();
+        // Left by a crashed daemon: PIDs are below 2^22.
+        fs::write(rundir.join("ovs-vswitchd.pid"), "99999999\n").unwrap();
+        fs::write(rundir.join("ovs-vswitchd.99999999.ctl"), "").unwrap();
+        let stale = find_socket("ovs-vswitchd", &rundir);
+
+        let sock_path = rundir.join(format!("ovs-vswitchd.{}.ctl", id()));
+        fs::write(&sock_path, "").unwrap();
+        let live = find_socket("ovs-vswitchd", &rundir);
+        fs::remove_dir_all(&rundir).unwrap();
+
+        assert!(matches!(stale, Err(Error::OvsNotRunning)));
+        assert_eq!(live.unwrap(), sock_path);
+    }
+
+    #[test]
+    fn run_trailing_newline() {
+        let server = FakeServer::new("run_trailing_newline", |_, _| Ok("line\n".to_string()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(ovs.run("cmd", None).unwrap().as_deref(), Some("line\n"));
+        assert_eq!(
+            ovs.run_trimmed("cmd", None).unwrap().as_deref(),
+            Some("line")
+        );
+    }
+
+    #[test]
+    fn daemon_closed() {
+        let path = env::temp_dir().join(format!("ovs-unixctl-daemon-closed-{}.ctl", id()));
+        let _ = fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let server = std::thread::spawn(move || {
+            // Read the request and close the connection without replying.
+            let (sock, _) = listener.accept().unwrap();
+            let _: serde_json::Value = serde_json::Deserializer::from_reader(&sock)
+                .into_iter()
+                .next()
+                .unwrap()
+                .unwrap();
+        });
+
+        let mut ovs = OvsUnixCtl::unix(&path, None).unwrap();
+        let res = ovs.run("bond/show", Some(&["bond0"]));
+        server.join().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(res, Err(Error::DaemonClosed { cmd }) if cmd == "bond/show"));
+    }
+
+    #[test]
+    fn custom_transport() {
+        #[derive(Default)]
+        struct Replay {
+            responses: Arc<Mutex<Vec<&'static str>>>,
+            sent: Arc<Mutex<Vec<serde_json::Value>>>,
+        }
+
+        impl fmt::Display for Replay {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("replay")
+            }
+        }
+
+        impl CustomTransport for Replay {
+            fn connect(&mut self) -> Result<Box<dyn CustomStream>> {
+                Ok(Box::new(ReplayStream {
+                    responses: self.responses.clone(),
+                    sent: self.sent.clone(),
+                }))
+            }
+        }
+
+        struct ReplayStream {
+            responses: Arc<Mutex<Vec<&'static str>>>,
+            sent: Arc<Mutex<Vec<serde_json::Value>>>,
+        }
+
+        impl CustomStream for ReplayStream {
+            fn send(&mut self, msg: serde_json::Value) -> Result<()> {
+                self.sent.lock().unwrap().push(msg);
+                Ok(())
+            }
+
+            fn recv(&mut self) -> Result<serde_json::Value> {
+                let id = self.sent.lock().unwrap().last().unwrap()["id"].clone();
+                match self.responses.lock().unwrap().pop() {
+                    Some(result) => Ok(json!({"result": result, "error": null, "id": id})),
+                    None => Err(Error::Timeout),
+                }
+            }
+        }
+
+        let replay = Replay::default();
+        let (responses, sent) = (replay.responses.clone(), replay.sent.clone());
+        *responses.lock().unwrap() = vec!["ovs-vswitchd (Open vSwitch) 3.3.0\n", "br0\n"];
+
+        let mut ovs = OvsUnixCtl::custom(replay).unwrap();
+        assert_eq!(ovs.ofproto_list().unwrap(), vec!["br0".to_string()]);
+        assert_eq!(ovs.version().unwrap(), OvsVersion::new(3, 3, 0));
+        assert!(matches!(ovs.run("version", None), Err(Error::Timeout)));
+        assert_eq!(ovs.socket_path(), None);
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0]["method"], "ofproto/list");
+    }
+
+    #[test]
+    fn ping() {
+        let server = FakeServer::new(
+            "ping",
+            |_, _| Ok("ovs-vswitchd (Open vSwitch) 3.3.0".into()),
+        );
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert!(ovs.ping().unwrap() < Duration::from_secs(1));
+        drop(ovs);
+        assert_eq!(server.requests(), vec![("version".to_string(), vec![])]);
+
+        let path = env::temp_dir().join(format!("ovs-unixctl-ping-closed-{}.ctl", id()));
+        let _ = fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let server = std::thread::spawn(move || drop(listener.accept().unwrap()));
+
+        let mut ovs = OvsUnixCtl::unix(&path, None).unwrap();
+        server.join().unwrap();
+        let res = ovs.ping();
+        fs::remove_file(&path).unwrap();
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn timeout() {
+        let server = FakeServer::raw("timeout", |_| vec![]);
+        let mut ovs = OvsUnixCtl::unix(server.path(), Some(Duration::from_millis(100))).unwrap();
+        assert!(matches!(ovs.run("cmd", None), Err(Error::Timeout)));
+    }
+
+    #[test]
+    fn run_batch_errors() {
+        let server = FakeServer::new("run_batch_errors", |cmd, params| match cmd {
+            "fail" => Err("failed".to_string()),
+            _ => Ok(format!("{cmd}({})", params.join(","))),
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let results = ovs
+            .run_batch(&[("first", &["a", "b"]), ("fail", &["c"]), ("last", &[])])
+            .unwrap();
+        drop(ovs);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().as_deref(), Some("first(a,b)"));
+        assert!(
+            matches!(&results[1], Err(Error::Command { cmd, params, error })
+            if cmd == "fail" && params == "c" && error == "failed")
+        );
+        assert_eq!(results[2].as_ref().unwrap().as_deref(), Some("last()"));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn run_until() {
+        let server = FakeServer::new("run_until", |_, _| Ok("ok".to_string()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let deadline = Instant::now();
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(matches!(
+            ovs.run_until("expired", None, deadline),
+            Err(Error::Timeout)
+        ));
+        assert_eq!(
+            ovs.run_until("cmd", None, Instant::now() + Duration::from_secs(1))
+                .unwrap()
+                .as_deref(),
+            Some("ok")
+        );
+        drop(ovs);
+
+        // The expired call didn't reach the daemon.
+        assert_eq!(server.requests(), vec![("cmd".to_string(), vec![])]);
+    }
+
+    #[test]
+    fn binary_param() {
+        assert_eq!(encode_binary_param(&[]), "");
+        assert_eq!(
+            encode_binary_param(&[0x00, 0x0a, 0xff, 0x08, 0x00, 0x45]),
+            "000aff080045"
+        );
+
+        let server = FakeServer::new("binary_param", |_, _| Ok(String::default()));
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        ovs.run_with_binary("ofproto/trace", &["br0", "in_port=1"], &[0xde, 0xad])
+            .unwrap();
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![(
+                "ofproto/trace".to_string(),
+                vec![
+                    "br0".to_string(),
+                    "in_port=1".to_string(),
+                    "dead".to_string()
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn run_timeout() {
+        let server = FakeServer::new("run_timeout", |method, _| {
+            if method == "slow" {
+                std::thread::sleep(Duration::from_millis(150));
+            }
+            Ok("ok".to_string())
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), Some(Duration::from_millis(50))).unwrap();
+        assert_eq!(
+            ovs.run_timeout("slow", None, Duration::from_secs(1))
+                .unwrap()
+                .as_deref(),
+            Some("ok")
+        );
+        assert_eq!(ovs.run("fast", None).unwrap().as_deref(), Some("ok"));
+        // The default timeout was restored.
+        assert!(matches!(ovs.run("slow", None), Err(Error::Timeout)));
+    }
+
+    #[test]
+    fn run_cached() {
+        let mut count = 0;
+        let server = FakeServer::new("run_cached", move |_, _| {
+            count += 1;
+            Ok(count.to_string())
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let ttl = Duration::from_millis(100);
+        assert_eq!(
+            ovs.run_cached("cmd", None, ttl).unwrap().as_deref(),
+            Some("1")
+        );
+        // Cache hit.
+        assert_eq!(
+            ovs.run_cached("cmd", None, ttl).unwrap().as_deref(),
+            Some("1")
+        );
+        // Different parameters.
+        assert_eq!(
+            ovs.run_cached("cmd", Some(&["arg"]), ttl)
+                .unwrap()
+                .as_deref(),
+            Some("2")
+        );
+        std::thread::sleep(ttl);
+        // Expired.
+        assert_eq!(
+            ovs.run_cached("cmd", None, ttl).unwrap().as_deref(),
+            Some("3")
+        );
+        // The other expired result was evicted.
+        assert_eq!(ovs.cache.len(), 1);
+        drop(ovs);
+
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn call_value() {
+        let server = FakeServer::raw("call_value", |msg| match msg["method"].as_str() {
+            Some("echo") => vec![json!({
+                "result": {"method": "echo", "params": msg["params"]},
+                "error": null,
+                "id": msg["id"],
+            })],
+            _ => vec![json!({"result": null, "error": "unknown method", "id": msg["id"]})],
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        let params = [json!(42), json!({"key": ["a", "b"]}), json!(null)];
+
+        assert_eq!(
+            ovs.call_value("echo", &params).unwrap(),
+            json!({"method": "echo", "params": [42, {"key": ["a", "b"]}, null]})
+        );
+        assert!(matches!(
+            ovs.call_value("other", &params[..1]),
+            Err(Error::Command { cmd, params, error })
+                if cmd == "other" && params == "42" && error == "unknown method"
+        ));
+    }
+
+    #[test]
+    fn run_json() {
+        let server = FakeServer::raw("run_json", |msg| {
+            let result = match msg["method"].as_str() {
+                Some("set-options") => json!(""),
+                _ => json!({"ports": [1, 2]}),
+            };
+            vec![json!({"result": result, "error": null, "id": msg["id"]})]
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(
+            ovs.run_json("dpif/show", None).unwrap(),
+            json!({"ports": [1, 2]})
+        );
+        drop(ovs);
+
+        let requests = server.requests();
+        assert_eq!(
+            requests,
+            vec![
+                (
+                    "set-options".to_string(),
+                    vec!["--format".to_string(), "json".to_string()]
+                ),
+                ("dpif/show".to_string(), vec![]),
+                (
+                    "set-options".to_string(),
+                    vec!["--format".to_string(), "text".to_string()]
+                ),
+            ]
+        );
+        // Compact output only.
+        assert!(!requests
+            .iter()
+            .any(|(_, params)| params.iter().any(|p| p == "--pretty")));
+    }
+
+    #[test]
+    fn run_json_error() {
+        let server = FakeServer::raw("run_json_error", |msg| match msg["method"].as_str() {
+            Some("set-options") => vec![json!({"result": "", "error": null, "id": msg["id"]})],
+            _ => vec![json!({"result": null, "error": "unknown command", "id": msg["id"]})],
+        });
+        // The format is restored after a failing command, whose error is returned.
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert!(ovs.run_json("bad/cmd", None).is_err());
+        drop(ovs);
+        assert_eq!(
+            server.requests().last().unwrap(),
+            &(
+                "set-options".to_string(),
+                vec!["--format".to_string(), "text".to_string()]
+            )
+        );
+    }
 }
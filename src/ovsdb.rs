@@ -0,0 +1,67 @@
+//! OVSDB server helpers
+//!
+//! Commands specific to ovsdb-server. Connect to it using [`OvsUnixCtl::with_target`], e.g.:
+//! `OvsUnixCtl::with_target("ovsdb-server".into(), None)`.
+
+use crate::{parse, parse::InvalidResponse, OvsUnixCtl, Result};
+
+impl OvsUnixCtl {
+    /// Runs the "ovsdb-server/get-active-ovsdb-server" command and returns the remote of the
+    /// active server this one replicates from, or `None` if none is configured.
+    pub fn get_active_server(&mut self) -> Result<Option<String>> {
+        match self.run("ovsdb-server/get-active-ovsdb-server", None)? {
+            Some(response) => parse::ovsdb::active_server(&response),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the version of the schema of the given database, e.g: "Open_vSwitch".
+    ///
+    /// ovsdb-server doesn't expose it through unixctl: this uses the OVSDB "get_schema" method,
+    /// hence must be used on a connection to one of the database sockets (e.g: `db.sock`, see
+    /// [`OvsUnixCtl::unix`]), not to the unixctl one. An unknown database results in
+    /// [`Error::Command`](crate::Error::Command).
+    pub fn db_version(&mut self, db: &str) -> Result<(u32, u32, u32)> {
+        let schema = self.call_value("get_schema", &[db.into()])?;
+        let version = schema["version"].as_str().ok_or_else(|| {
+            InvalidResponse::new("get_schema", &schema.to_string())
+                .error("missing schema version".to_string())
+        })?;
+        parse::ovsdb::schema_version(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{testing::FakeServer, Error};
+
+    #[test]
+    fn db_version() {
+        let server = FakeServer::raw("db_version", |msg| {
+            vec![match msg["params"][0].as_str() {
+                Some("Open_vSwitch") => json!({
+                    "result": {"name": "Open_vSwitch", "version": "8.3.0", "tables": {}},
+                    "error": null,
+                    "id": msg["id"],
+                }),
+                _ => json!({
+                    "result": null,
+                    "error": {
+                        "error": "unknown database",
+                        "details": "get_schema request specifies unknown database Unknown",
+                    },
+                    "id": msg["id"],
+                }),
+            }]
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(ovs.db_version("Open_vSwitch").unwrap(), (8, 3, 0));
+        assert!(matches!(
+            ovs.db_version("Unknown"),
+            Err(Error::Command { error, .. }) if error.starts_with("unknown database")
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+//! Parsers of bond command outputs.
+
+use super::InvalidResponse;
+use crate::{
+    bond::{BondEntry, BondShow, BondSlave},
+    Result,
+};
+
+/// Parses the output of the "bond/list" command and returns the bonds.
+///
+/// The header line is skipped if present. The recirculation id column, which is not printed by
+/// all versions, is detected using the header or, without one, the number of columns.
+pub fn list(response: &str) -> Result<Vec<BondEntry>> {
+    let invalid = InvalidResponse::new("bond/list", response);
+
+    let mut lines = response.lines().filter(|l| !l.trim().is_empty()).peekable();
+    let mut has_recirc = None;
+    if let Some(header) = lines.next_if(|l| l.starts_with("bond\ttype")) {
+        has_recirc = Some(header.contains("recirc"));
+    }
+
+    lines
+        .map(|line| {
+            let columns: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let (name, bond_type, recirc, slaves) =
+                match (has_recirc.unwrap_or(columns.len() > 3), &columns[..]) {
+                    (true, [name, bond_type, recirc, slaves]) => {
+                        (name, bond_type, Some(recirc), slaves)
+                    }
+                    (false, [name, bond_type, slaves]) => (name, bond_type, None, slaves),
+                    _ => return Err(invalid.error(format!("invalid line: {line}"))),
+                };
+            Ok(BondEntry {
+                name: name.to_string(),
+                bond_type: bond_type.to_string(),
+                // A negative recirculation id means recirculation isn't used.
+                recirc_id: match recirc {
+                    Some(recirc) if !recirc.starts_with('-') => Some(invalid.parse(recirc)?),
+                    _ => None,
+                },
+                slaves: slaves
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Parses the output of the "bond/show" command for a single bond and returns its details.
+///
+/// Both the current ("member") and older ("slave") terminologies are supported. Unknown lines
+/// are ignored.
+pub fn show(response: &str) -> Result<BondShow> {
+    let invalid = InvalidResponse::new("bond/show", response);
+    let invalid_line = |line: &str| invalid.error(format!("invalid line: {line}"));
+
+    let mut bond = BondShow::default();
+    for line in response.lines().filter(|l| !l.trim().is_empty()) {
+        // Lines describing a member are indented below it.
+        if line.starts_with(char::is_whitespace) {
+            let slave = bond.slaves.last_mut().ok_or_else(|| invalid_line(line))?;
+            let line = line.trim();
+            if line == "active member" || line == "active slave" {
+                bond.active_slave = Some(slave.name.clone());
+            } else if let Some(may_enable) = line.strip_prefix("may_enable:") {
+                slave.may_enable = invalid.parse(may_enable).map_err(|_| invalid_line(line))?;
+            } else if let Some(hash) = line.strip_prefix("hash ") {
+                let (hash, load) = hash.split_once(':').ok_or_else(|| invalid_line(line))?;
+                let load = load
+                    .trim()
+                    .strip_suffix("kB load")
+                    .ok_or_else(|| invalid_line(line))?;
+                slave.hashes.push((
+                    invalid.parse(hash).map_err(|_| invalid_line(line))?,
+                    invalid.parse(load).map_err(|_| invalid_line(line))?,
+                ));
+            }
+            continue;
+        }
+
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" ----"))
+        {
+            bond.name = name.to_string();
+        } else if let Some(slave) = line
+            .strip_prefix("member ")
+            .or_else(|| line.strip_prefix("slave "))
+        {
+            let (name, state) = slave.split_once(':').ok_or_else(|| invalid_line(line))?;
+            bond.slaves.push(BondSlave {
+                name: name.trim().to_string(),
+                enabled: match state.trim() {
+                    "enabled" => true,
+                    "disabled" => false,
+                    _ => return Err(invalid_line(line)),
+                },
+                ..Default::default()
+            });
+        } else if let Some((key, val)) = line.split_once(':') {
+            match key {
+                "bond_mode" => bond.bond_mode = val.trim().to_string(),
+                "lacp_status" => bond.lacp_status = val.trim().to_string(),
+                _ => (),
+            }
+        }
+    }
+
+    if bond.bond_mode.is_empty() {
+        return Err(invalid.error("bond_mode not found".to_string()));
+    }
+    Ok(bond)
+}
+
+/// Parses the output of the "bond/hash" command and returns the hash.
+pub fn hash(response: &str) -> Result<u32> {
+    InvalidResponse::new("bond/hash", response).parse(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list() {
+        let entry = |name: &str, bond_type: &str, recirc_id, slaves: &[&str]| BondEntry {
+            name: name.to_string(),
+            bond_type: bond_type.to_string(),
+            recirc_id,
+            slaves: slaves.iter().map(|s| s.to_string()).collect(),
+        };
+
+        assert_eq!(
+            list("bond\ttype\trecircID\tmembers\nbond0\tbalance-tcp\t1\tp2, p1\nbond1\tactive-backup\t-1\tp3, p4\n")
+                .unwrap(),
+            vec![
+                entry("bond0", "balance-tcp", Some(1), &["p2", "p1"]),
+                entry("bond1", "active-backup", None, &["p3", "p4"]),
+            ]
+        );
+        assert_eq!(
+            list("bond\ttype\tslaves\nbond0\tbalance-slb\tp2, p1\n").unwrap(),
+            vec![entry("bond0", "balance-slb", None, &["p2", "p1"])]
+        );
+        assert_eq!(
+            list("bond0\tbalance-tcp\t1\tp1\n").unwrap(),
+            vec![entry("bond0", "balance-tcp", Some(1), &["p1"])]
+        );
+        assert_eq!(list("bond\ttype\trecircID\tmembers\n").unwrap(), vec![]);
+        assert!(list("bond\ttype\trecircID\tmembers\nbond0\tbalance-tcp\n").is_err());
+    }
+
+    #[test]
+    fn parse_show() {
+        let bond = show(
+            "---- bond0 ----
+bond_mode: balance-slb
+bond may use recirculation: no, Recirc-ID : -1
+bond-hash-basis: 0
+lb_output action: disabled, bond-id: -1
+updelay: 0 ms
+downdelay: 0 ms
+next rebalance: 6537 ms
+lacp_status: off
+lacp_fallback_ab: false
+active-backup primary: <none>
+active member mac: 52:54:00:12:34:56(p1)
+
+member p1: enabled
+  active member
+  may_enable: true
+  hash 12: 5 kB load
+  hash 231: 0 kB load
+
+member p2: disabled
+  may_enable: false
+",
+        )
+        .unwrap();
+        assert_eq!(
+            bond,
+            BondShow {
+                name: "bond0".to_string(),
+                bond_mode: "balance-slb".to_string(),
+                lacp_status: "off".to_string(),
+                active_slave: Some("p1".to_string()),
+                slaves: vec![
+                    BondSlave {
+                        name: "p1".to_string(),
+                        enabled: true,
+                        may_enable: true,
+                        hashes: vec![(12, 5), (231, 0)],
+                    },
+                    BondSlave {
+                        name: "p2".to_string(),
+                        enabled: false,
+                        may_enable: false,
+                        hashes: vec![],
+                    },
+                ],
+            }
+        );
+
+        // Older versions.
+        let bond = show(
+            "---- bond0 ----
+bond_mode: active-backup
+lacp_status: off
+
+slave p1: enabled
+	active slave
+	may_enable: true
+",
+        )
+        .unwrap();
+        assert_eq!(bond.active_slave.as_deref(), Some("p1"));
+        assert_eq!(bond.slaves[0].name, "p1");
+
+        match show("bond_mode: balance-slb\nmember p1: unknown\n") {
+            Err(crate::Error::OvsInvalidResponse { error, .. }) => {
+                assert_eq!(error, "invalid line: member p1: unknown")
+            }
+            res => panic!("unexpected result {res:?}"),
+        }
+        assert!(show("member p1: enabled\n  hash x: 5 kB load\n").is_err());
+        assert!(show("no such bond\n").is_err());
+    }
+
+    #[test]
+    fn parse_hash() {
+        assert_eq!(hash("217\n").unwrap(), 217);
+        assert!(hash("").is_err());
+        assert!(hash("hash: 217").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Parsers of coverage command outputs.
+
+use super::InvalidResponse;
+use crate::{coverage::CoverageCounter, Result};
+
+/// Parses the output of the "coverage/show" command and returns the coverage counters.
+///
+/// Only counter lines, i.e: the ones reporting a total, are collected. The header and other
+/// informative lines (e.g: "N events never hit") are ignored.
+pub fn show(response: &str) -> Result<Vec<CoverageCounter>> {
+    let invalid = InvalidResponse::new("coverage/show", response);
+
+    response
+        .lines()
+        .filter_map(|l| l.split_once("total:"))
+        .map(|(counter, total)| {
+            let mut fields = counter.split_whitespace();
+            let name = fields
+                .next()
+                .ok_or_else(|| invalid.error(format!("missing counter name: {counter}")))?;
+            let rates = fields
+                .map(|r| invalid.parse(r.trim_end_matches("/sec")))
+                .collect::<Result<Vec<f64>>>()?;
+            Ok(CoverageCounter {
+                name: name.to_string(),
+                last_rate: rates.first().copied().unwrap_or_default(),
+                minute_rate: rates.get(1).copied(),
+                hour_rate: rates.get(2).copied(),
+                total: invalid.parse(total)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_show() {
+        let counters = show(
+            "Event coverage, avg rate over last: 5 seconds, last minute, last hour,  hash=1e2c1c6f:
+bridge_reconfigure         0.0/sec     0.000/sec        0.0003/sec   total: 1
+netlink_sent               1.2/sec     0.850/sec        0.7503/sec   total: 2701
+103 events never hit
+",
+        )
+        .unwrap();
+        assert_eq!(
+            counters,
+            vec![
+                CoverageCounter {
+                    name: "bridge_reconfigure".to_string(),
+                    last_rate: 0.0,
+                    minute_rate: Some(0.0),
+                    hour_rate: Some(0.0003),
+                    total: 1,
+                },
+                CoverageCounter {
+                    name: "netlink_sent".to_string(),
+                    last_rate: 1.2,
+                    minute_rate: Some(0.85),
+                    hour_rate: Some(0.7503),
+                    total: 2701,
+                },
+            ]
+        );
+
+        assert!(show("netlink_sent 1.2/sec total: many").is_err());
+        assert!(show("netlink_sent x/sec total: 1").is_err());
+    }
+}
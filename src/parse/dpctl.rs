@@ -0,0 +1,963 @@
+//! Parsers of datapath (dpctl) command outputs.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+use super::InvalidResponse;
+use crate::{
+    dpctl::{
+        BriefFlow, CtEntry, CtStats, CtTuple, Datapath, DpPort, DpPortStats, Flow, FlowKind,
+        FlowMetric,
+    },
+    Result,
+};
+
+/// Parses the output of the "dpctl/dump-flows" command and returns the datapath flows.
+///
+/// Lines that are not flows (e.g: the per-thread headers of the userspace datapath) are ignored.
+pub fn flows(response: &str) -> Result<Vec<Flow>> {
+    flow_list("dpctl/dump-flows", response)
+}
+
+/// Parses the output of the "dpif/dump-flows" command and returns the datapath flows of the bridge.
+///
+/// The output is the one of "dpctl/dump-flows", restricted to the flows of the bridge: lines that
+/// are not flows are ignored as well.
+pub fn dpif_flows(response: &str) -> Result<Vec<Flow>> {
+    flow_list("dpif/dump-flows", response)
+}
+
+/// Parses the output of the "dpctl/dump-flows" command and returns the `n` flows with the highest
+/// number of packets or bytes, sorted in descending order.
+///
+/// Only `n` flows are kept while parsing, regardless of the number of flows in the output. Flows
+/// with the same statistics are kept in the order of the output.
+pub fn top_flows(response: &str, n: usize, by: FlowMetric) -> Result<Vec<Flow>> {
+    let invalid = InvalidResponse::new("dpctl/dump-flows", response);
+
+    // Min-heap of the top flows, the first one being the one to evict.
+    // Not preallocated, `n` can be usize::MAX to get all the flows sorted.
+    let mut top = BinaryHeap::new();
+    for (index, line) in flow_lines(response).enumerate() {
+        let flow = flow(&invalid, line)?;
+        let metric = match by {
+            FlowMetric::Packets => flow.packets,
+            FlowMetric::Bytes => flow.bytes,
+        };
+        top.push(Reverse(RankedFlow {
+            metric,
+            index,
+            flow,
+        }));
+        if top.len() > n {
+            top.pop();
+        }
+    }
+
+    Ok(top
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| ranked.flow)
+        .collect())
+}
+
+/// Flow ranked by one of its statistics. Flows with the same statistics are ranked by position in
+/// the output, the first ones being the highest.
+struct RankedFlow {
+    metric: u64,
+    index: usize,
+    flow: Flow,
+}
+
+impl RankedFlow {
+    fn key(&self) -> (u64, Reverse<usize>) {
+        (self.metric, Reverse(self.index))
+    }
+}
+
+impl PartialEq for RankedFlow {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for RankedFlow {}
+
+impl PartialOrd for RankedFlow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedFlow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Parses the flows of a "dpctl/dump-flows"-like command output.
+fn flow_list(cmd: &str, response: &str) -> Result<Vec<Flow>> {
+    let invalid = InvalidResponse::new(cmd, response);
+
+    flow_lines(response)
+        .map(|line| flow(&invalid, line))
+        .collect()
+}
+
+/// Returns the flow lines of a "dpctl/dump-flows"-like command output, skipping the others.
+fn flow_lines(response: &str) -> impl Iterator<Item = &str> {
+    response
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.contains("actions:"))
+}
+
+/// Removes the ufid flows might be prefixed by.
+fn strip_ufid(line: &str) -> &str {
+    match line.strip_prefix("ufid:") {
+        Some(l) => l.split_once(", ").map(|(_, l)| l).unwrap_or(l),
+        None => line,
+    }
+}
+
+/// Parses a single flow line of the "dpctl/dump-flows" command output.
+fn flow(invalid: &InvalidResponse, line: &str) -> Result<Flow> {
+    let line = strip_ufid(line);
+    let (match_, rest) = line
+        .split_once(", packets:")
+        .ok_or_else(|| invalid.error(format!("packets not found: {line}")))?;
+    let (stats, actions) = rest
+        .split_once("actions:")
+        .ok_or_else(|| invalid.error(format!("actions not found: {line}")))?;
+
+    let kind = match match_fields(match_)
+        .iter()
+        .any(|(_, _, mask)| mask.is_some())
+    {
+        true => FlowKind::Megaflow,
+        false => FlowKind::ExactMatch,
+    };
+    let mut flow = Flow {
+        match_: match_.to_string(),
+        actions: actions.trim().to_string(),
+        kind,
+        ..Default::default()
+    };
+    let mut stats = stats.split(',').map(str::trim).filter(|s| !s.is_empty());
+    flow.packets = invalid.parse(stats.next().unwrap_or_default())?;
+    let mut bytes = None;
+    for stat in stats {
+        match stat.split_once(':') {
+            Some(("bytes", val)) => bytes = Some(invalid.parse(val)?),
+            Some(("used", val)) => flow.used = invalid.duration(val)?,
+            _ => (),
+        }
+    }
+    flow.bytes = bytes.ok_or_else(|| invalid.error(format!("bytes not found: {line}")))?;
+    Ok(flow)
+}
+
+/// Parses the output of the "dpctl/dump-flows" command and returns the matches and actions of the
+/// datapath flows. Statistics, if any, are skipped.
+pub fn flows_brief(response: &str) -> Result<Vec<BriefFlow>> {
+    let invalid = InvalidResponse::new("dpctl/dump-flows", response);
+
+    flow_lines(response)
+        .map(|line| {
+            let line = strip_ufid(line);
+            let (head, actions) = line
+                .split_once("actions:")
+                .ok_or_else(|| invalid.error(format!("actions not found: {line}")))?;
+            let match_ = match head.split_once(", packets:") {
+                Some((match_, _)) => match_,
+                None => head.trim_end().trim_end_matches(','),
+            };
+            Ok(BriefFlow {
+                match_: match_.to_string(),
+                actions: actions.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parses the output of the "dpctl/show" command and returns the datapaths.
+pub fn show(response: &str) -> Result<Vec<Datapath>> {
+    let invalid = InvalidResponse::new("dpctl/show", response);
+
+    let mut datapaths: Vec<Datapath> = Vec::new();
+    for line in response.lines().filter(|l| !l.trim().is_empty()) {
+        // Datapath names are the only non-indented lines.
+        if !line.starts_with(char::is_whitespace) {
+            datapaths.push(Datapath {
+                name: line.trim().trim_end_matches(':').to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let dp = datapaths
+            .last_mut()
+            .ok_or_else(|| invalid.error(format!("line outside of a datapath: {line}")))?;
+        let line = line.trim();
+        if let Some(lookups) = line.strip_prefix("lookups:") {
+            for stat in lookups.split_whitespace() {
+                match stat.split_once(':') {
+                    Some(("hit", val)) => dp.lookups.hit = invalid.parse(val)?,
+                    Some(("missed", val)) => dp.lookups.missed = invalid.parse(val)?,
+                    Some(("lost", val)) => dp.lookups.lost = invalid.parse(val)?,
+                    _ => (),
+                }
+            }
+        } else if let Some(masks) = line.strip_prefix("masks:") {
+            for stat in masks.split_whitespace() {
+                match stat.split_once(':') {
+                    Some(("hit", val)) => dp.masks.hit = invalid.parse(val)?,
+                    Some(("total", val)) => dp.masks.total = invalid.parse(val)?,
+                    _ => (),
+                }
+            }
+        } else if let Some(flows) = line.strip_prefix("flows:") {
+            dp.flows = invalid.parse(flows)?;
+        } else if line.starts_with("RX ") || line.starts_with("TX ") {
+            let port = dp
+                .ports
+                .last_mut()
+                .ok_or_else(|| invalid.error(format!("statistics outside of a port: {line}")))?;
+            port_stats(
+                &invalid,
+                port.stats.get_or_insert_with(Default::default),
+                line,
+            )?;
+        } else if let Some(port) = line.strip_prefix("port ") {
+            let (port_no, desc) = port
+                .split_once(':')
+                .ok_or_else(|| invalid.error(format!("invalid port: {line}")))?;
+            let desc = desc.trim();
+            let (name, type_) = match desc.split_once(' ') {
+                Some((name, type_)) => (
+                    name,
+                    Some(
+                        type_
+                            .trim()
+                            .trim_start_matches('(')
+                            .trim_end_matches(')')
+                            .to_string(),
+                    ),
+                ),
+                None => (desc, None),
+            };
+            dp.ports.push(DpPort {
+                port_no: invalid.parse(port_no)?,
+                name: name.to_string(),
+                type_,
+                stats: None,
+            });
+            dp.n_ports += 1;
+        }
+    }
+
+    Ok(datapaths)
+}
+
+/// Parses a line of port statistics, e.g: `RX packets:10 errors:0 dropped:0 overruns:0 frame:0` or
+/// `RX bytes:1234 (1.2 KiB)  TX bytes:0`. Counters are attributed to the direction preceding them;
+/// unknown ones, as well as the human-readable sizes, are ignored.
+fn port_stats(invalid: &InvalidResponse, stats: &mut DpPortStats, line: &str) -> Result<()> {
+    let mut rx = true;
+    for token in line.split_whitespace() {
+        match token {
+            "RX" => rx = true,
+            "TX" => rx = false,
+            _ => {
+                let Some((name, val)) = token.split_once(':') else {
+                    continue;
+                };
+                let stat = match (rx, name) {
+                    (true, "packets") => &mut stats.rx_packets,
+                    (true, "bytes") => &mut stats.rx_bytes,
+                    (true, "dropped") => &mut stats.rx_dropped,
+                    (true, "errors") => &mut stats.rx_errors,
+                    (false, "packets") => &mut stats.tx_packets,
+                    (false, "bytes") => &mut stats.tx_bytes,
+                    (false, "dropped") => &mut stats.tx_dropped,
+                    (false, "errors") => &mut stats.tx_errors,
+                    _ => continue,
+                };
+                // Counters the port doesn't support are shown as "?".
+                *stat = match val {
+                    "?" => 0,
+                    val => invalid.parse(val)?,
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses the output of the "dpctl/show" command and returns the number of datapath flows. If
+/// several datapaths are shown, their flow counts are summed.
+pub fn flow_count(response: &str) -> Result<u64> {
+    let invalid = InvalidResponse::new("dpctl/show", response);
+
+    let counts = response
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("flows:"))
+        .map(|count| invalid.parse::<u64>(count))
+        .collect::<Result<Vec<u64>>>()?;
+    if counts.is_empty() {
+        return Err(invalid.error("flows not found".to_string()));
+    }
+    Ok(counts.iter().sum())
+}
+
+/// Parses the output of the "dpctl/dump-conntrack" command and returns the connection tracking
+/// entries.
+///
+/// Fields this parser doesn't know of, e.g: the ICMP ids or the start time, are ignored.
+pub fn conntrack(response: &str) -> Result<Vec<CtEntry>> {
+    let invalid = InvalidResponse::new("dpctl/dump-conntrack", response);
+
+    response
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|line| {
+            let invalid_line = || invalid.error(format!("invalid entry: {line}"));
+            let fields = split_top_level(line, ',');
+            let (protocol, fields) = fields.split_first().ok_or_else(invalid_line)?;
+
+            let (mut orig, mut reply) = (None, None);
+            let mut entry = (None, None, None, None);
+            for field in fields {
+                let (key, val) = field.split_once('=').ok_or_else(invalid_line)?;
+                let inner = || {
+                    val.strip_prefix('(')
+                        .and_then(|v| v.strip_suffix(')'))
+                        .ok_or_else(invalid_line)
+                };
+                match key {
+                    "orig" => orig = Some(ct_tuple(&invalid, line, inner()?)?),
+                    "reply" => reply = Some(ct_tuple(&invalid, line, inner()?)?),
+                    "mark" => entry.0 = Some(invalid.parse(val)?),
+                    "zone" => entry.1 = Some(invalid.parse(val)?),
+                    "timeout" => entry.2 = Some(invalid.parse(val)?),
+                    "protoinfo" => {
+                        entry.3 = inner()?
+                            .split(',')
+                            .find_map(|info| match info.split_once('=') {
+                                Some(("state" | "state_orig", state)) => Some(state.to_string()),
+                                _ => None,
+                            })
+                    }
+                    _ => (),
+                }
+            }
+
+            let (mark, zone, timeout, state) = entry;
+            Ok(CtEntry {
+                protocol: protocol.trim().to_string(),
+                orig: orig.ok_or_else(invalid_line)?,
+                reply: reply.ok_or_else(invalid_line)?,
+                state,
+                mark,
+                zone,
+                timeout,
+            })
+        })
+        .collect()
+}
+
+/// Parses a connection tracking tuple, e.g: `src=10.0.0.1,dst=10.0.0.2,sport=1234,dport=80`.
+fn ct_tuple(invalid: &InvalidResponse, line: &str, tuple: &str) -> Result<CtTuple> {
+    let invalid_tuple = || invalid.error(format!("invalid tuple: {line}"));
+
+    let (mut src, mut dst, mut sport, mut dport) = (None, None, None, None);
+    for field in tuple.split(',') {
+        let (key, val) = field.split_once('=').ok_or_else(invalid_tuple)?;
+        match key {
+            "src" => src = Some(val.parse().map_err(|_| invalid_tuple())?),
+            "dst" => dst = Some(val.parse().map_err(|_| invalid_tuple())?),
+            "sport" => sport = Some(val.parse().map_err(|_| invalid_tuple())?),
+            "dport" => dport = Some(val.parse().map_err(|_| invalid_tuple())?),
+            _ => (),
+        }
+    }
+    Ok(CtTuple {
+        src: src.ok_or_else(invalid_tuple)?,
+        dst: dst.ok_or_else(invalid_tuple)?,
+        sport,
+        dport,
+    })
+}
+
+/// Parses the output of the "dpctl/ct-stats-show" command and returns the connection tracking
+/// statistics.
+///
+/// Only the top-level counters are collected, the per-state details shown in verbose mode are
+/// ignored.
+pub fn ct_stats(response: &str) -> Result<CtStats> {
+    let invalid = InvalidResponse::new("dpctl/ct-stats-show", response);
+
+    let indent = |l: &str| l.len() - l.trim_start().len();
+    let total_line = response
+        .lines()
+        .find(|l| l.trim().starts_with("Total:"))
+        .ok_or_else(|| invalid.error("total not found".to_string()))?;
+
+    let mut stats = CtStats::default();
+    for line in response
+        .lines()
+        .filter(|l| indent(l) == indent(total_line) && !l.trim().is_empty())
+    {
+        let (name, val) = line
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+        match name {
+            "Total" => stats.total = invalid.parse(val)?,
+            proto => {
+                stats
+                    .protocols
+                    .insert(proto.to_string(), invalid.parse(val)?);
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Parses the output of the "dpctl/ct-get-maxconns" command and returns the maximum number of
+/// connection tracking entries.
+pub fn ct_maxconns(response: &str) -> Result<u32> {
+    InvalidResponse::new("dpctl/ct-get-maxconns", response).parse(response)
+}
+
+/// Splits a string on the given separator, ignoring the separators found inside parentheses or
+/// braces.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth = depth.saturating_sub(1),
+            c if c == sep && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+    parts.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+/// Parses a flow match string and returns its fields as `(field, value, mask)` tuples.
+pub(crate) fn match_fields(match_: &str) -> Vec<(String, String, Option<String>)> {
+    let mut fields = Vec::new();
+    push_fields(&mut fields, None, match_);
+    fields
+}
+
+fn push_fields(fields: &mut Vec<(String, String, Option<String>)>, prefix: Option<&str>, s: &str) {
+    let name = |n: &str| match prefix {
+        Some(prefix) => format!("{prefix}.{}", n.trim()),
+        None => n.trim().to_string(),
+    };
+    let value_mask = |v: &str| match v.rsplit_once('/') {
+        Some((value, mask)) => (value.to_string(), Some(mask.to_string())),
+        None => (v.to_string(), None),
+    };
+
+    for field in split_top_level(s, ',') {
+        let field = field.trim();
+        let eq = field.find('=');
+        let paren = field.find('(');
+        match (eq, paren) {
+            // key(...): either a list of subfields or a single value.
+            (eq, Some(paren)) if eq.is_none_or(|eq| paren < eq) && field.ends_with(')') => {
+                let inner = &field[paren + 1..field.len() - 1];
+                let key = name(&field[..paren]);
+                if inner.contains('=') {
+                    push_fields(fields, Some(&key), inner);
+                } else {
+                    let (value, mask) = value_mask(inner);
+                    fields.push((key, value, mask));
+                }
+            }
+            // key=value.
+            (Some(eq), _) => {
+                let (value, mask) = value_mask(&field[eq + 1..]);
+                fields.push((name(&field[..eq]), value, mask));
+            }
+            // Bare key, e.g: "ip".
+            _ => fields.push((name(field), String::default(), None)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        dpctl::{DpLookups, DpMasks},
+        Error,
+    };
+
+    fn field(f: &str, v: &str, m: Option<&str>) -> (String, String, Option<String>) {
+        (f.to_string(), v.to_string(), m.map(String::from))
+    }
+
+    #[test]
+    fn parse_ct_stats() {
+        let stats = ct_stats(
+            "Connections Stats:
+    Total: 12
+    TCP: 10
+      ESTABLISHED: 8
+      TIME_WAIT: 2
+    UDP: 2
+",
+        )
+        .unwrap();
+        assert_eq!(stats.total, 12);
+        assert_eq!(
+            stats.protocols,
+            [("TCP".to_string(), 10), ("UDP".to_string(), 2)].into()
+        );
+
+        assert!(ct_stats("Connections Stats:\n").is_err());
+        assert_eq!(ct_maxconns("3000000\n").unwrap(), 3000000);
+    }
+
+    #[test]
+    fn parse_top_flows() {
+        // Bytes are a permutation of 0..100 so the top flows are known.
+        let dump: String = (0..100u64)
+            .map(|i| {
+                format!(
+                    "in_port({i}), packets:{}, bytes:{}, used:never, actions:drop\n",
+                    i % 10,
+                    (i * 37) % 100,
+                )
+            })
+            .collect();
+
+        let top = top_flows(&dump, 3, FlowMetric::Bytes).unwrap();
+        assert_eq!(
+            top.iter()
+                .map(|f| (f.match_.as_str(), f.bytes))
+                .collect::<Vec<_>>(),
+            vec![
+                ("in_port(27)", 99),
+                ("in_port(54)", 98),
+                ("in_port(81)", 97)
+            ]
+        );
+
+        // Ties are kept in the order of the output.
+        let top = top_flows(&dump, 2, FlowMetric::Packets).unwrap();
+        assert_eq!(
+            top.iter().map(|f| f.match_.as_str()).collect::<Vec<_>>(),
+            vec!["in_port(9)", "in_port(19)"]
+        );
+
+        assert!(top_flows(&dump, 0, FlowMetric::Bytes).unwrap().is_empty());
+        assert_eq!(top_flows(&dump, 200, FlowMetric::Bytes).unwrap().len(), 100);
+        assert_eq!(
+            top_flows(&dump, usize::MAX, FlowMetric::Bytes)
+                .unwrap()
+                .len(),
+            100
+        );
+    }
+
+    #[test]
+    fn parse_flows_brief() {
+        let expected = vec![
+            BriefFlow {
+                match_: "recirc_id(0),in_port(2),eth_type(0x0800),ipv4(frag=no)".to_string(),
+                actions: "1".to_string(),
+            },
+            BriefFlow {
+                match_: "recirc_id(0),in_port(1),eth_type(0x0806)".to_string(),
+                actions: "drop".to_string(),
+            },
+        ];
+        assert_eq!(
+            flows_brief(
+                "recirc_id(0),in_port(2),eth_type(0x0800),ipv4(frag=no), actions:1
+ufid:1b2a2e3c-0b0c-4d5e-8f90-a1b2c3d4e5f6, recirc_id(0),in_port(1),eth_type(0x0806), actions:drop
+"
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            flows_brief(
+                "recirc_id(0),in_port(2),eth_type(0x0800),ipv4(frag=no), packets:3, bytes:294, used:0.5s, actions:1
+recirc_id(0),in_port(1),eth_type(0x0806), packets:0, bytes:0, used:never, actions:drop
+"
+            )
+            .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_flows() {
+        let dump = flows(
+            "flow-dump from the main thread:\n\
+             recirc_id(0),in_port(2),eth_type(0x0806), packets:3, bytes:126, used:0.360s, actions:1\n\
+             ufid:5b9d6e8c-9b0e-4d43-8a5b-1bba84a4d6d1, recirc_id(0),in_port(1),eth_type(0x0800),ipv4(frag=no), packets:0, bytes:0, used:never, flags:S, actions:ct(commit,zone=5),2\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            dump,
+            vec![
+                Flow {
+                    match_: "recirc_id(0),in_port(2),eth_type(0x0806)".to_string(),
+                    packets: 3,
+                    bytes: 126,
+                    used: Some(Duration::from_millis(360)),
+                    actions: "1".to_string(),
+                    kind: FlowKind::ExactMatch,
+                },
+                Flow {
+                    match_: "recirc_id(0),in_port(1),eth_type(0x0800),ipv4(frag=no)".to_string(),
+                    packets: 0,
+                    bytes: 0,
+                    used: None,
+                    actions: "ct(commit,zone=5),2".to_string(),
+                    kind: FlowKind::ExactMatch,
+                },
+            ]
+        );
+
+        assert!(flows("in_port(1), packets:foo, bytes:0, used:never, actions:drop").is_err());
+        // The byte count is mandatory.
+        assert!(flows("in_port(1), packets:0, used:never, actions:drop").is_err());
+    }
+
+    #[test]
+    fn parse_flows_large() {
+        // Errors must only be built on failure: they hold a copy of the whole output, which made
+        // parsing quadratic in the size of the dump.
+        let dump: String = (0..50_000)
+            .map(|i| {
+                format!(
+                    "ufid:{i:08x}-0000-0000-0000-000000000000, recirc_id(0),in_port({}),eth_type(0x0800),ipv4(src=10.0.{}.{},frag=no), packets:{i}, bytes:{}, used:0.5s, actions:2\n",
+                    i % 16,
+                    i / 256 % 256,
+                    i % 256,
+                    i * 64,
+                )
+            })
+            .collect();
+        let start = std::time::Instant::now();
+        let dump_flows = flows(&dump).unwrap();
+        assert_eq!(dump_flows.len(), 50_000);
+        assert_eq!(dump_flows[49_999].bytes, 49_999 * 64);
+        assert_eq!(flows_brief(&dump).unwrap().len(), 50_000);
+        assert_eq!(
+            top_flows(&dump, 10, FlowMetric::Bytes).unwrap()[0].bytes,
+            49_999 * 64
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn parse_show() {
+        let datapaths = show(
+            "system@ovs-system:\n  \
+               lookups: hit:1234 missed:56 lost:2\n  \
+               flows: 42\n  \
+               masks: hit:2345 total:3 hit/pkt:1.83\n  \
+               port 0: ovs-system (internal)\n  \
+               port 1: eth0\n  \
+               port 2: vxlan_sys_4789 (vxlan: packet_type=ptap)\n\
+             netdev@ovs-netdev:\n  \
+               lookups: hit:0 missed:0 lost:0\n  \
+               flows: 0\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            datapaths,
+            vec![
+                Datapath {
+                    name: "system@ovs-system".to_string(),
+                    lookups: DpLookups {
+                        hit: 1234,
+                        missed: 56,
+                        lost: 2,
+                    },
+                    masks: DpMasks {
+                        hit: 2345,
+                        total: 3,
+                    },
+                    flows: 42,
+                    n_ports: 3,
+                    ports: vec![
+                        DpPort {
+                            port_no: 0,
+                            name: "ovs-system".to_string(),
+                            type_: Some("internal".to_string()),
+                            stats: None,
+                        },
+                        DpPort {
+                            port_no: 1,
+                            name: "eth0".to_string(),
+                            type_: None,
+                            stats: None,
+                        },
+                        DpPort {
+                            port_no: 2,
+                            name: "vxlan_sys_4789".to_string(),
+                            type_: Some("vxlan: packet_type=ptap".to_string()),
+                            stats: None,
+                        },
+                    ],
+                },
+                Datapath {
+                    name: "netdev@ovs-netdev".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        // Port statistics, with the extended statistics of DPDK ports.
+        assert_eq!(
+            show(
+                "netdev@ovs-netdev:\n  \
+                   lookups: hit:10 missed:2 lost:0\n  \
+                   flows: 1\n  \
+                   port 1: dpdk0 (dpdk: configured_rx_queues=1, mtu=1500)\n    \
+                     RX packets:100 errors:1 dropped:2 overruns:? frame:?\n    \
+                     TX packets:50 errors:0 dropped:? aborted:? carrier:?\n    \
+                     collisions:?\n    \
+                     RX bytes:6400 (6.2 KiB)  TX bytes:3200 (3.1 KiB)\n    \
+                     statistics:\n      \
+                       ovs_rx_qos_drops:0\n      \
+                       rx_missed_errors:4\n",
+            )
+            .unwrap()[0]
+                .ports,
+            vec![DpPort {
+                port_no: 1,
+                name: "dpdk0".to_string(),
+                type_: Some("dpdk: configured_rx_queues=1, mtu=1500".to_string()),
+                stats: Some(DpPortStats {
+                    rx_packets: 100,
+                    rx_bytes: 6400,
+                    rx_dropped: 2,
+                    rx_errors: 1,
+                    tx_packets: 50,
+                    tx_bytes: 3200,
+                    tx_dropped: 0,
+                    tx_errors: 0,
+                }),
+            }]
+        );
+
+        assert!(show("  flows: 1\n").is_err());
+        assert!(show("system@ovs-system:\n  port x: eth0\n").is_err());
+        assert!(show("system@ovs-system:\n  RX packets:1\n").is_err());
+        assert!(show("system@ovs-system:\n  port 0: eth0\n    RX packets:x\n").is_err());
+    }
+
+    #[test]
+    fn parse_conntrack() {
+        let entries = conntrack(
+            "tcp,orig=(src=10.0.0.1,dst=10.0.0.2,sport=34567,dport=80),reply=(src=10.0.0.2,dst=10.0.0.1,sport=80,dport=34567),zone=5,mark=1,timeout=431999,protoinfo=(state=ESTABLISHED)\n\
+             udp,orig=(src=fd00::1,dst=fd00::2,sport=5353,dport=53),reply=(src=fd00::2,dst=fd00::1,sport=53,dport=5353),id=42\n\
+             icmp,orig=(src=10.0.0.1,dst=10.0.0.2,id=1234,type=8,code=0),reply=(src=10.0.0.2,dst=10.0.0.1,id=1234,type=0,code=0)\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                CtEntry {
+                    protocol: "tcp".to_string(),
+                    orig: CtTuple {
+                        src: "10.0.0.1".parse().unwrap(),
+                        dst: "10.0.0.2".parse().unwrap(),
+                        sport: Some(34567),
+                        dport: Some(80),
+                    },
+                    reply: CtTuple {
+                        src: "10.0.0.2".parse().unwrap(),
+                        dst: "10.0.0.1".parse().unwrap(),
+                        sport: Some(80),
+                        dport: Some(34567),
+                    },
+                    state: Some("ESTABLISHED".to_string()),
+                    mark: Some(1),
+                    zone: Some(5),
+                    timeout: Some(431999),
+                },
+                CtEntry {
+                    protocol: "udp".to_string(),
+                    orig: CtTuple {
+                        src: "fd00::1".parse().unwrap(),
+                        dst: "fd00::2".parse().unwrap(),
+                        sport: Some(5353),
+                        dport: Some(53),
+                    },
+                    reply: CtTuple {
+                        src: "fd00::2".parse().unwrap(),
+                        dst: "fd00::1".parse().unwrap(),
+                        sport: Some(53),
+                        dport: Some(5353),
+                    },
+                    state: None,
+                    mark: None,
+                    zone: None,
+                    timeout: None,
+                },
+                CtEntry {
+                    protocol: "icmp".to_string(),
+                    orig: CtTuple {
+                        src: "10.0.0.1".parse().unwrap(),
+                        dst: "10.0.0.2".parse().unwrap(),
+                        sport: None,
+                        dport: None,
+                    },
+                    reply: CtTuple {
+                        src: "10.0.0.2".parse().unwrap(),
+                        dst: "10.0.0.1".parse().unwrap(),
+                        sport: None,
+                        dport: None,
+                    },
+                    state: None,
+                    mark: None,
+                    zone: None,
+                    timeout: None,
+                },
+            ]
+        );
+
+        let line = "tcp,orig=(src=10.0.0.x,dst=10.0.0.2),reply=(src=10.0.0.2,dst=10.0.0.1)";
+        assert!(matches!(
+            conntrack(line),
+            Err(Error::OvsInvalidResponse { error, .. }) if error == format!("invalid tuple: {line}")
+        ));
+        assert!(conntrack("tcp,orig=(src=10.0.0.1,dst=10.0.0.2)").is_err());
+        assert!(conntrack("tcp,orig=(src=10.0.0.1),reply=(src=10.0.0.2,dst=10.0.0.1)").is_err());
+    }
+
+    #[test]
+    fn parse_flow_count() {
+        assert_eq!(
+            flow_count(
+                "system@ovs-system:\n  \
+                   lookups: hit:1234 missed:56 lost:0\n  \
+                   flows: 42\n  \
+                   masks: hit:2345 total:3 hit/pkt:1.83\n  \
+                   cache: hit:1000 hit-rate:77.47%\n  \
+                   caches:\n    \
+                     masks-cache: size:256\n  \
+                   port 0: ovs-system (internal)\n  \
+                   port 1: br0 (internal)\n",
+            )
+            .unwrap(),
+            42
+        );
+        assert!(flow_count("system@ovs-system:\n  lookups: hit:0 missed:0 lost:0\n").is_err());
+        assert!(flow_count("system@ovs-system:\n  flows: many\n").is_err());
+    }
+
+    #[test]
+    fn parse_dpif_flows() {
+        let dump = dpif_flows(
+            "flow-dump from pmd on cpu core: 2\n\
+             recirc_id(0),in_port(1),packet_type(ns=0,id=0),eth_type(0x0800),ipv4(frag=no), packets:3, bytes:294, used:0.876s, actions:2\n\
+             recirc_id(0),in_port(2),packet_type(ns=0,id=0),eth_type(0x0806), packets:0, bytes:0, used:never, actions:drop\n",
+        )
+        .unwrap();
+        assert_eq!(dump.len(), 2);
+        assert_eq!(
+            dump[0].match_,
+            "recirc_id(0),in_port(1),packet_type(ns=0,id=0),eth_type(0x0800),ipv4(frag=no)"
+        );
+        assert_eq!(
+            (dump[0].packets, dump[0].bytes, dump[0].used),
+            (3, 294, Some(Duration::from_millis(876)))
+        );
+        assert_eq!(dump[0].actions, "2");
+        assert_eq!((dump[1].packets, dump[1].used), (0, None));
+        assert_eq!(dump[1].actions, "drop");
+
+        for invalid in [
+            "in_port(1), used:never, actions:drop",
+            "in_port(1), packets:3, used:never, actions:drop",
+            "in_port(1), packets:x, bytes:0, used:never, actions:drop",
+        ] {
+            assert!(
+                matches!(dpif_flows(invalid), Err(Error::OvsInvalidResponse { .. })),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn flow_kind() {
+        let dump = flows(
+            "recirc_id(0),in_port(2),eth_type(0x0800),ipv4(src=10.0.0.0/255.255.255.0,frag=no), packets:1, bytes:98, used:1.5s, actions:3\n\
+             recirc_id(0),in_port(2),eth_type(0x0800),ipv4(src=10.0.0.1,frag=no), packets:1, bytes:98, used:1.5s, actions:3\n",
+        )
+        .unwrap();
+        assert_eq!(dump[0].kind, FlowKind::Megaflow);
+        assert_eq!(dump[1].kind, FlowKind::ExactMatch);
+    }
+
+    #[test]
+    fn exact_match_fields() {
+        assert_eq!(
+            match_fields(
+                "recirc_id(0),in_port(2),eth(src=52:54:00:12:34:56,dst=52:54:00:65:43:21),eth_type(0x0800),ipv4(src=10.0.0.1,dst=10.0.0.2,proto=1,frag=no)"
+            ),
+            vec![
+                field("recirc_id", "0", None),
+                field("in_port", "2", None),
+                field("eth.src", "52:54:00:12:34:56", None),
+                field("eth.dst", "52:54:00:65:43:21", None),
+                field("eth_type", "0x0800", None),
+                field("ipv4.src", "10.0.0.1", None),
+                field("ipv4.dst", "10.0.0.2", None),
+                field("ipv4.proto", "1", None),
+                field("ipv4.frag", "no", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn masked_match_fields() {
+        assert_eq!(
+            match_fields(
+                "skb_mark(0/0xff),eth_type(0x0800),ipv4(src=10.0.0.0/255.255.255.0,dst=10.0.0.2,frag=no)"
+            ),
+            vec![
+                field("skb_mark", "0", Some("0xff")),
+                field("eth_type", "0x0800", None),
+                field("ipv4.src", "10.0.0.0", Some("255.255.255.0")),
+                field("ipv4.dst", "10.0.0.2", None),
+                field("ipv4.frag", "no", None),
+            ]
+        );
+        assert_eq!(
+            match_fields("ip,nw_src=10.0.0.0/0xffffff00,tp_dst=80"),
+            vec![
+                field("ip", "", None),
+                field("nw_src", "10.0.0.0", Some("0xffffff00")),
+                field("tp_dst", "80", None),
+            ]
+        );
+    }
+}
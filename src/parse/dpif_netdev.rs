@@ -0,0 +1,354 @@
+//! Parsers of userspace datapath (dpif-netdev) command outputs.
+
+use std::cmp::Reverse;
+
+use super::InvalidResponse;
+use crate::{
+    dpif_netdev::{PmdRxq, PmdStats, RxqUsage, SubtableInfo},
+    Result,
+};
+
+/// Parses the output of the "dpif-netdev/subtable-lookup-info-get" command and returns the
+/// available subtable lookup implementations, sorted by priority (highest first).
+///
+/// Both the `name (Use count: N, Priority: P)` and `prio P: name: N` line formats are supported.
+pub fn subtable_lookup_info(response: &str) -> Result<Vec<SubtableInfo>> {
+    let invalid = InvalidResponse::new("dpif-netdev/subtable-lookup-info-get", response);
+
+    let mut subtables = Vec::new();
+    for line in response.lines().map(str::trim) {
+        if let Some(line) = line.strip_prefix("prio ") {
+            match line.split(':').collect::<Vec<&str>>()[..] {
+                [prio, name, usage] => subtables.push(SubtableInfo {
+                    prio: invalid.parse(prio)?,
+                    name: name.trim().to_string(),
+                    usage: invalid.parse(usage)?,
+                }),
+                _ => return Err(invalid.error(format!("invalid line: {line}"))),
+            }
+        } else if let Some((name, info)) = line
+            .strip_suffix(')')
+            .and_then(|l| l.split_once(" (Use count:"))
+        {
+            let (usage, prio) = info
+                .split_once(", Priority:")
+                .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+            subtables.push(SubtableInfo {
+                prio: invalid.parse(prio)?,
+                name: name.trim().to_string(),
+                usage: invalid.parse(usage)?,
+            });
+        }
+    }
+
+    subtables.sort_by_key(|s| Reverse(s.prio));
+    Ok(subtables)
+}
+
+/// Parses the output of the "dpif-netdev/pmd-rxq-show" command and returns the receive queues
+/// polled by each PMD thread.
+pub fn pmd_rxq_show(response: &str) -> Result<Vec<PmdRxq>> {
+    let invalid = InvalidResponse::new("dpif-netdev/pmd-rxq-show", response);
+
+    let mut pmds: Vec<PmdRxq> = Vec::new();
+    for line in response.lines().map(str::trim) {
+        if let Some(pmd) = line
+            .strip_prefix("pmd thread numa_id")
+            .and_then(|l| l.strip_suffix(':'))
+        {
+            let (numa_id, core_id) = pmd
+                .split_once("core_id")
+                .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+            pmds.push(PmdRxq {
+                numa_id: invalid.parse(numa_id)?,
+                core_id: invalid.parse(core_id)?,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        // Other lines describe the current PMD thread, if any.
+        let Some(pmd) = pmds.last_mut() else {
+            continue;
+        };
+        if let Some(isolated) = line.strip_prefix("isolated") {
+            pmd.isolated = invalid.parse(isolated.trim_start_matches([' ', ':']))?;
+        } else if let Some(rxq) = line.strip_prefix("port:") {
+            let (port, rest) = rxq
+                .split_once("queue-id:")
+                .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+            let (queue, usage) = rest
+                .split_once("pmd usage:")
+                .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+            let (queue, state) = queue.trim().split_once(' ').unwrap_or((queue, ""));
+            let usage = match usage.trim() {
+                "NOT AVAIL" => None,
+                usage => Some(invalid.parse(usage.trim_end_matches('%'))?),
+            };
+
+            pmd.rxqs.push(RxqUsage {
+                port: port.trim().to_string(),
+                queue: invalid.parse(queue)?,
+                enabled: !state.contains("disabled"),
+                usage,
+                cross_numa: None,
+            });
+        }
+    }
+
+    Ok(pmds)
+}
+
+/// Parses the output of the "dpif-netdev/pmd-stats-show" command and returns the statistics of
+/// each thread.
+///
+/// Both the current format and the one of older versions (`miss`, `lost` and `polling cycles`
+/// lines) are supported. Statistics this parser doesn't know of are ignored.
+pub fn pmd_stats_show(response: &str) -> Result<Vec<PmdStats>> {
+    let invalid = InvalidResponse::new("dpif-netdev/pmd-stats-show", response);
+
+    let mut pmds: Vec<PmdStats> = Vec::new();
+    for line in response.lines().map(str::trim) {
+        if line == "main thread:" {
+            pmds.push(PmdStats::default());
+            continue;
+        }
+        if let Some(pmd) = line
+            .strip_prefix("pmd thread numa_id")
+            .and_then(|l| l.strip_suffix(':'))
+        {
+            let (numa_id, core_id) = pmd
+                .split_once("core_id")
+                .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+            pmds.push(PmdStats {
+                numa_id: Some(invalid.parse(numa_id)?),
+                core_id: Some(invalid.parse(core_id)?),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        // Other lines describe the current thread, if any.
+        let (Some(pmd), Some((key, val))) = (pmds.last_mut(), line.split_once(':')) else {
+            continue;
+        };
+        let stat = match key {
+            "packets received" => &mut pmd.packets_received,
+            "packet recirculations" => &mut pmd.recirculations,
+            "emc hits" => &mut pmd.emc_hits,
+            "smc hits" => &mut pmd.smc_hits,
+            "megaflow hits" => &mut pmd.megaflow_hits,
+            "miss with success upcall" | "miss" => &mut pmd.misses,
+            "miss with failed upcall" | "lost" => &mut pmd.lost,
+            "idle cycles" | "polling cycles" => &mut pmd.idle_cycles,
+            "processing cycles" => &mut pmd.busy_cycles,
+            _ => continue,
+        };
+        // Cycles are followed by their percentage, e.g: "1234 (12.34%)".
+        *stat = invalid.parse(val.split_whitespace().next().unwrap_or_default())?;
+    }
+
+    Ok(pmds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pmd_rxq_show() {
+        let pmds = pmd_rxq_show(
+            "Displaying last 60 seconds pmd usage %\n\
+             pmd thread numa_id 0 core_id 2:\n  \
+               isolated : false\n  \
+               port: dpdk0             queue-id:  0 (enabled)   pmd usage: 22 %\n  \
+               port: vhu1              queue-id:  1 (disabled)  pmd usage:  0 %\n  \
+               overhead:  1 %\n\
+             pmd thread numa_id 1 core_id 3:\n  \
+               isolated : true\n  \
+               port: dpdk1             queue-id:  0 (enabled)   pmd usage: NOT AVAIL\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            pmds,
+            vec![
+                PmdRxq {
+                    numa_id: 0,
+                    core_id: 2,
+                    isolated: false,
+                    rxqs: vec![
+                        RxqUsage {
+                            port: "dpdk0".to_string(),
+                            queue: 0,
+                            enabled: true,
+                            usage: Some(22),
+                            cross_numa: None,
+                        },
+                        RxqUsage {
+                            port: "vhu1".to_string(),
+                            queue: 1,
+                            enabled: false,
+                            usage: Some(0),
+                            cross_numa: None,
+                        },
+                    ],
+                },
+                PmdRxq {
+                    numa_id: 1,
+                    core_id: 3,
+                    isolated: true,
+                    rxqs: vec![RxqUsage {
+                        port: "dpdk1".to_string(),
+                        queue: 0,
+                        enabled: true,
+                        usage: None,
+                        cross_numa: None,
+                    }],
+                },
+            ]
+        );
+
+        // Older versions don't print the queue state.
+        assert_eq!(
+            pmd_rxq_show(
+                "pmd thread numa_id 0 core_id 2:\n  \
+                   isolated : false\n  \
+                   port: dpdk0  queue-id:  3  pmd usage: 5 %\n",
+            )
+            .unwrap()[0]
+                .rxqs,
+            vec![RxqUsage {
+                port: "dpdk0".to_string(),
+                queue: 3,
+                enabled: true,
+                usage: Some(5),
+                cross_numa: None,
+            }]
+        );
+
+        assert!(pmd_rxq_show("pmd thread numa_id x core_id 2:").is_err());
+    }
+
+    #[test]
+    fn parse_pmd_stats_show() {
+        let pmds = pmd_stats_show(
+            "pmd thread numa_id 0 core_id 2:\n  \
+               packets received: 1000\n  \
+               packet recirculations: 200\n  \
+               avg. datapath passes per packet: 1.20\n  \
+               phwol hits: 0\n  \
+               mfex opt hits: 0\n  \
+               simple match hits: 0\n  \
+               emc hits: 900\n  \
+               smc hits: 50\n  \
+               megaflow hits: 240\n  \
+               avg. subtable lookups per megaflow hit: 1.50\n  \
+               miss with success upcall: 8\n  \
+               miss with failed upcall: 2\n  \
+               avg. packets per output batch: 2.00\n  \
+               idle cycles: 123456789 (90.00%)\n  \
+               processing cycles: 13717421 (10.00%)\n  \
+               avg cycles per packet: 137174.21 (137174210/1000)\n  \
+               avg processing cycles per packet: 13717.42 (13717421/1000)\n\
+             main thread:\n  \
+               packets received: 10\n  \
+               emc hits: 10\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            pmds,
+            vec![
+                PmdStats {
+                    numa_id: Some(0),
+                    core_id: Some(2),
+                    packets_received: 1000,
+                    recirculations: 200,
+                    emc_hits: 900,
+                    smc_hits: 50,
+                    megaflow_hits: 240,
+                    misses: 8,
+                    lost: 2,
+                    idle_cycles: 123456789,
+                    busy_cycles: 13717421,
+                },
+                PmdStats {
+                    packets_received: 10,
+                    emc_hits: 10,
+                    ..Default::default()
+                },
+            ]
+        );
+
+        // Older versions.
+        assert_eq!(
+            pmd_stats_show(
+                "pmd thread numa_id 1 core_id 3:\n\
+                 \temc hits:5\n\
+                 \tmegaflow hits:3\n\
+                 \tmiss:2\n\
+                 \tlost:1\n\
+                 \tpolling cycles:400 (80.00%)\n\
+                 \tprocessing cycles:100 (20.00%)\n",
+            )
+            .unwrap(),
+            vec![PmdStats {
+                numa_id: Some(1),
+                core_id: Some(3),
+                emc_hits: 5,
+                megaflow_hits: 3,
+                misses: 2,
+                lost: 1,
+                idle_cycles: 400,
+                busy_cycles: 100,
+                ..Default::default()
+            }]
+        );
+
+        assert!(pmd_stats_show("pmd thread numa_id 0 core_id 2:\n  emc hits: x\n").is_err());
+    }
+
+    #[test]
+    fn parse_subtable_lookup_info() {
+        let expected = vec![
+            SubtableInfo {
+                prio: 5,
+                name: "avx512_gather".to_string(),
+                usage: 3,
+            },
+            SubtableInfo {
+                prio: 1,
+                name: "generic".to_string(),
+                usage: 7,
+            },
+            SubtableInfo {
+                prio: 0,
+                name: "autovalidator".to_string(),
+                usage: 0,
+            },
+        ];
+
+        assert_eq!(
+            subtable_lookup_info(
+                "prio 0: autovalidator: 0\n\
+                 prio 1: generic: 7\n\
+                 prio 5: avx512_gather: 3\n",
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            subtable_lookup_info(
+                "Available dpcls implementations:\n  \
+                 autovalidator (Use count: 0, Priority: 0)\n  \
+                 generic (Use count: 7, Priority: 1)\n  \
+                 avx512_gather (Use count: 3, Priority: 5)\n",
+            )
+            .unwrap(),
+            expected
+        );
+
+        assert!(subtable_lookup_info("prio x: generic: 7").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Parsers of MAC learning table (fdb) command outputs.
+
+use super::InvalidResponse;
+use crate::{fdb::FdbEntry, Result};
+
+/// OpenFlow number of the local port.
+const OFPP_LOCAL: u32 = 0xfffe;
+
+/// Parses the output of the "fdb/show" command and returns the MAC learning table entries.
+///
+/// The header line is skipped if present.
+pub fn show(response: &str) -> Result<Vec<FdbEntry>> {
+    let invalid = InvalidResponse::new("fdb/show", response);
+
+    response
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .skip_while(|l| l.trim().starts_with("port"))
+        .map(
+            |line| match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [port, vlan, mac, age] => Ok(FdbEntry {
+                    port: match port {
+                        "LOCAL" => OFPP_LOCAL,
+                        port => invalid.parse(port)?,
+                    },
+                    vlan: invalid.parse(vlan)?,
+                    mac: invalid.parse(mac)?,
+                    age: match age {
+                        "static" => u32::MAX,
+                        age => invalid.parse(age)?,
+                    },
+                }),
+                _ => Err(invalid.error(format!("invalid line: {line}"))),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fdb::MacAddr, Error};
+
+    #[test]
+    fn parse_show() {
+        let entries = show(
+            " port  VLAN  MAC                Age
+    1     0  52:54:00:12:34:56    3
+LOCAL    10  52:54:00:ab:cd:ef   12
+    2     0  52:54:00:00:00:01  static
+",
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                FdbEntry {
+                    port: 1,
+                    vlan: 0,
+                    mac: MacAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]),
+                    age: 3,
+                },
+                FdbEntry {
+                    port: OFPP_LOCAL,
+                    vlan: 10,
+                    mac: MacAddr([0x52, 0x54, 0x00, 0xab, 0xcd, 0xef]),
+                    age: 12,
+                },
+                FdbEntry {
+                    port: 2,
+                    vlan: 0,
+                    mac: MacAddr([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]),
+                    age: u32::MAX,
+                },
+            ]
+        );
+
+        assert_eq!(
+            show(" port  VLAN  MAC                Age\n").unwrap(),
+            vec![]
+        );
+        assert!(matches!(
+            show("    1     0  52:54:00:12:34    3\n"),
+            Err(Error::OvsInvalidResponse { cmd, .. }) if cmd == "fdb/show"
+        ));
+        assert!(show("    1     0  52:54:00:12:34:56\n").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Parsers of LACP command outputs.
+
+use super::InvalidResponse;
+use crate::{
+    lacp::{LacpInfo, LacpMember, LacpState},
+    Result,
+};
+
+/// Parses the output of the "lacp/show" command and returns the LACP details of the bonds.
+///
+/// Both the current ("member") and older ("slave") terminologies are supported. Unknown keys
+/// are rejected.
+pub fn show(response: &str) -> Result<Vec<LacpInfo>> {
+    let invalid = InvalidResponse::new("lacp/show", response);
+    let invalid_line = |line: &str| invalid.error(format!("invalid line: {line}"));
+
+    let mut bonds: Vec<LacpInfo> = Vec::new();
+    for line in response.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(bond) = line
+            .strip_prefix("----")
+            .and_then(|l| l.strip_suffix("----"))
+        {
+            bonds.push(LacpInfo {
+                bond: bond.trim().to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let bond = bonds.last_mut().ok_or_else(|| invalid_line(line))?;
+        if let Some(member) = line
+            .strip_prefix("member:")
+            .or_else(|| line.strip_prefix("slave:"))
+        {
+            let (name, status) = member.rsplit_once(':').ok_or_else(|| invalid_line(line))?;
+            let mut status = status.split_whitespace();
+            bond.members.push(LacpMember {
+                name: name.trim().to_string(),
+                status: status.next().unwrap_or_default().to_string(),
+                attached: status.next() == Some("attached"),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let (key, val) = line.split_once(':').ok_or_else(|| invalid_line(line))?;
+        let val = val.trim();
+        // Keys of the bond come first, the ones of its members follow them.
+        let Some(member) = bond.members.last_mut() else {
+            match key {
+                "status" => {
+                    bond.active = val.split_whitespace().any(|s| s == "active");
+                    bond.negotiated = val.split_whitespace().any(|s| s == "negotiated");
+                }
+                "sys_id" => bond.sys_id = invalid.parse(val)?,
+                "sys_priority" => bond.sys_priority = invalid.parse(val)?,
+                "aggregation key" | "lacp_time" | "lacp_fallback_ab" => (),
+                _ => return Err(invalid.error(format!("unknown key: {line}"))),
+            }
+            continue;
+        };
+
+        let (actor, key) = match key.split_once(' ') {
+            Some(("actor", key)) => (&mut member.actor, key),
+            Some(("partner", key)) => (&mut member.partner, key),
+            _ => {
+                match key {
+                    "may_enable" => member.may_enable = invalid.parse(val)?,
+                    "port_id" | "port_priority" => (),
+                    _ => return Err(invalid.error(format!("unknown key: {line}"))),
+                }
+                continue;
+            }
+        };
+        match key {
+            "sys_id" => actor.sys_id = invalid.parse(val)?,
+            "sys_priority" => actor.sys_priority = invalid.parse(val)?,
+            "port_id" => actor.port_id = invalid.parse(val)?,
+            "port_priority" => actor.port_priority = invalid.parse(val)?,
+            "key" => actor.key = invalid.parse(val)?,
+            "state" => actor.state = state(&invalid, val)?,
+            _ => return Err(invalid.error(format!("unknown key: {line}"))),
+        }
+    }
+
+    Ok(bonds)
+}
+
+/// Parses LACP state flags, given either as the names of the flags which are set, e.g:
+/// `activity aggregation synchronized`, or as abbreviated flags with their value, e.g:
+/// `ACT:1 TIME:0 AGG:1`.
+fn state(invalid: &InvalidResponse, val: &str) -> Result<LacpState> {
+    let mut state = LacpState::default();
+    for flag in val.split_whitespace() {
+        let (name, set) = match flag.split_once(':') {
+            Some((name, "1")) => (name, true),
+            Some((name, "0")) => (name, false),
+            Some(_) => return Err(invalid.error(format!("invalid state flag: {flag}"))),
+            None => (flag, true),
+        };
+        let field = match name {
+            "activity" | "ACT" => &mut state.activity,
+            "timeout" | "TIME" => &mut state.timeout,
+            "aggregation" | "AGG" => &mut state.aggregation,
+            "synchronized" | "SYNC" => &mut state.synchronization,
+            "collecting" | "COL" => &mut state.collecting,
+            "distributing" | "DIST" => &mut state.distributing,
+            "defaulted" | "DEF" => &mut state.defaulted,
+            "expired" | "EXP" => &mut state.expired,
+            _ => return Err(invalid.error(format!("unknown state flag: {flag}"))),
+        };
+        *field = set;
+    }
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fdb::MacAddr, lacp::LacpActor, Error};
+
+    #[test]
+    fn parse_show() {
+        let bonds = show(
+            "---- bond0 ----\n  \
+               status: active negotiated\n  \
+               sys_id: aa:55:aa:55:00:01\n  \
+               sys_priority: 65534\n  \
+               aggregation key: 1\n  \
+               lacp_time: slow\n\
+             \n\
+             member: p0: current attached\n  \
+               port_id: 1\n  \
+               port_priority: 65535\n  \
+               may_enable: true\n\
+             \n  \
+               actor sys_id: aa:55:aa:55:00:01\n  \
+               actor sys_priority: 65534\n  \
+               actor port_id: 1\n  \
+               actor port_priority: 65535\n  \
+               actor key: 1\n  \
+               actor state: activity aggregation synchronized collecting distributing\n\
+             \n  \
+               partner sys_id: aa:55:aa:55:00:02\n  \
+               partner sys_priority: 65534\n  \
+               partner port_id: 5\n  \
+               partner port_priority: 65535\n  \
+               partner key: 3\n  \
+               partner state: ACT:1 TIME:1 AGG:1 SYNC:0 COL:0 DIST:0 DEF:0 EXP:0\n\
+             ---- bond1 ----\n  \
+               status: passive\n  \
+               sys_id: aa:55:aa:55:00:03\n  \
+               sys_priority: 1\n\
+             slave: p1: defaulted detached\n  \
+               may_enable: false\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            bonds,
+            vec![
+                LacpInfo {
+                    bond: "bond0".to_string(),
+                    active: true,
+                    negotiated: true,
+                    sys_id: MacAddr([0xaa, 0x55, 0xaa, 0x55, 0x00, 0x01]),
+                    sys_priority: 65534,
+                    members: vec![LacpMember {
+                        name: "p0".to_string(),
+                        status: "current".to_string(),
+                        attached: true,
+                        may_enable: true,
+                        actor: LacpActor {
+                            sys_id: MacAddr([0xaa, 0x55, 0xaa, 0x55, 0x00, 0x01]),
+                            sys_priority: 65534,
+                            port_id: 1,
+                            port_priority: 65535,
+                            key: 1,
+                            state: LacpState {
+                                activity: true,
+                                aggregation: true,
+                                synchronization: true,
+                                collecting: true,
+                                distributing: true,
+                                ..Default::default()
+                            },
+                        },
+                        partner: LacpActor {
+                            sys_id: MacAddr([0xaa, 0x55, 0xaa, 0x55, 0x00, 0x02]),
+                            sys_priority: 65534,
+                            port_id: 5,
+                            port_priority: 65535,
+                            key: 3,
+                            state: LacpState {
+                                activity: true,
+                                timeout: true,
+                                aggregation: true,
+                                ..Default::default()
+                            },
+                        },
+                    }],
+                },
+                LacpInfo {
+                    bond: "bond1".to_string(),
+                    sys_id: MacAddr([0xaa, 0x55, 0xaa, 0x55, 0x00, 0x03]),
+                    sys_priority: 1,
+                    members: vec![LacpMember {
+                        name: "p1".to_string(),
+                        status: "defaulted".to_string(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ]
+        );
+
+        assert!(show("").unwrap().is_empty());
+        for invalid in [
+            "  status: active\n",
+            "---- bond0 ----\n  color: blue\n",
+            "---- bond0 ----\nmember: p0: current attached\n  actor color: blue\n",
+            "---- bond0 ----\nmember: p0: current attached\n  actor state: dancing\n",
+            "---- bond0 ----\nmember: p0: current attached\n  actor state: ACT:2\n",
+            "---- bond0 ----\n  sys_id: not-a-mac\n",
+        ] {
+            assert!(
+                matches!(show(invalid), Err(Error::OvsInvalidResponse { .. })),
+                "{invalid}"
+            );
+        }
+    }
+}
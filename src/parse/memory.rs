@@ -0,0 +1,71 @@
+//! Parsers of memory command outputs.
+
+use std::collections::BTreeMap;
+
+use super::InvalidResponse;
+use crate::Result;
+
+/// Parses the output of the "memory/show" command and returns the reported figures, indexed by
+/// name.
+///
+/// The output is made of `name:value` pairs separated by whitespaces, e.g: `handlers:5 ports:3
+/// rules:12`. Some names contain spaces themselves, e.g: `udpif keys:10`.
+pub fn show(response: &str) -> Result<BTreeMap<String, u64>> {
+    let invalid = InvalidResponse::new("memory/show", response);
+
+    let mut figures = BTreeMap::new();
+    let mut name = Vec::new();
+    for token in response.split_whitespace() {
+        let Some((last, value)) = token.split_once(':') else {
+            // Part of a name containing spaces.
+            name.push(token);
+            continue;
+        };
+        name.push(last);
+        let value = value
+            .parse()
+            .map_err(|_| invalid.error(format!("invalid value: {token}")))?;
+        figures.insert(name.join(" "), value);
+        name.clear();
+    }
+    if !name.is_empty() {
+        return Err(invalid.error(format!("missing value: {}", name.join(" "))));
+    }
+
+    Ok(figures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_show() {
+        let figures = show(
+            "handlers:5 idl-cells-Open_vSwitch:341 ports:3 revalidators:3 rules:12 udpif keys:10\n",
+        )
+        .unwrap();
+        assert_eq!(
+            figures,
+            BTreeMap::from([
+                ("handlers".to_string(), 5),
+                ("idl-cells-Open_vSwitch".to_string(), 341),
+                ("ports".to_string(), 3),
+                ("revalidators".to_string(), 3),
+                ("rules".to_string(), 12),
+                ("udpif keys".to_string(), 10),
+            ])
+        );
+        assert!(show("").unwrap().is_empty());
+
+        assert!(matches!(
+            show("handlers:5 rules:"),
+            Err(crate::Error::OvsInvalidResponse { error, .. }) if error == "invalid value: rules:"
+        ));
+        assert!(matches!(
+            show("handlers:x"),
+            Err(crate::Error::OvsInvalidResponse { error, .. }) if error == "invalid value: handlers:x"
+        ));
+        assert!(show("handlers:5 rules").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Parsers of OVS command outputs.
+//!
+//! The typed helpers of [`OvsUnixCtl`](crate::OvsUnixCtl) use the functions in this module to
+//! parse the daemon responses. They are exposed so output captured elsewhere (e.g. from
+//! `ovs-appctl` runs or logs) can be parsed without a live connection.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::{error::Error, Result};
+
+pub mod bond;
+pub mod coverage;
+pub mod dpctl;
+pub mod dpif_netdev;
+pub mod fdb;
+pub mod lacp;
+pub mod memory;
+pub mod ofproto;
+pub mod ovn;
+pub mod ovsdb;
+pub mod route;
+pub mod vlog;
+
+/// Convenient struct to make it easy to build OvsInvalidResponse errors during parsing.
+pub(crate) struct InvalidResponse(pub(crate) String, pub(crate) String);
+impl InvalidResponse {
+    pub(crate) fn new(cmd: &str, response: &str) -> InvalidResponse {
+        InvalidResponse(cmd.to_string(), response.to_string())
+    }
+
+    pub(crate) fn error(&self, error: String) -> Error {
+        Error::OvsInvalidResponse {
+            cmd: self.0.clone(),
+            response: self.1.clone(),
+            error,
+        }
+    }
+
+    /// Parses a single value, returning an OvsInvalidResponse error on failure.
+    pub(crate) fn parse<T>(&self, val: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        val.trim()
+            .parse()
+            .map_err(|e| self.error(format!("can't parse {val}: {e}")))
+    }
+
+    /// Parses a duration (see [`parse_ovs_duration`]), returning an OvsInvalidResponse error
+    /// on failure.
+    pub(crate) fn duration(&self, val: &str) -> Result<Option<Duration>> {
+        parse_ovs_duration(val).map_err(|e| match e {
+            Error::OvsInvalidResponse { error, .. } => self.error(error),
+            e => e,
+        })
+    }
+}
+
+/// Parses a duration as printed by OVS, e.g: `0.123s`, `5ms` or `1h2m3s`. Returns `None` for
+/// `never`.
+pub(crate) fn parse_ovs_duration(s: &str) -> Result<Option<Duration>> {
+    let invalid = InvalidResponse::new("", s);
+    let s = s.trim();
+    if s == "never" {
+        return Ok(None);
+    }
+    if s.is_empty() {
+        return Err(invalid.error("empty duration".to_string()));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let num_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(|| invalid.error(format!("missing unit in duration {s}")))?;
+        let (num, unit) = rest.split_at(num_len);
+        let unit_len = unit
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(unit.len());
+        let (unit, next) = unit.split_at(unit_len);
+
+        let num: f64 = invalid.parse(num)?;
+        let secs = match unit {
+            "ms" => num / 1000.0,
+            "s" => num,
+            "m" => num * 60.0,
+            "h" => num * 3600.0,
+            "d" => num * 86400.0,
+            _ => return Err(invalid.error(format!("invalid unit in duration {s}"))),
+        };
+        total = Duration::try_from_secs_f64(secs)
+            .ok()
+            .and_then(|d| total.checked_add(d))
+            .ok_or_else(|| invalid.error(format!("duration out of range {s}")))?;
+        rest = next;
+    }
+
+    Ok(Some(total))
+}
+
+/// Parses the output of the common "list-commands" command and returns the list of commands and
+/// their arguments.
+///
+/// The header daemons print before the commands, if any, is skipped. Header lines are detected as
+/// starting with "The available commands are:" or ending with ':', which a command and its
+/// arguments never do. When commands are indented, as OVS does, leading lines that are not are
+/// also considered part of the header.
+///
+/// ```
+/// let commands = ovs_unixctl::parse::list_commands(
+///     "The available commands are:\n  bond/list\n  bond/show              [port]\n",
+/// )
+/// .unwrap();
+/// assert_eq!(commands[1], ("bond/show".to_string(), "[port]".to_string()));
+/// ```
+pub fn list_commands(response: &str) -> Result<Vec<(String, String)>> {
+    let indented = response.lines().any(|l| l.starts_with(char::is_whitespace));
+    Ok(response
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .skip_while(|l| {
+            is_list_commands_header(l) || (indented && !l.starts_with(char::is_whitespace))
+        })
+        .map(|l| {
+            let (cmd, args) = l.trim().split_once(char::is_whitespace).unwrap_or((l, ""));
+            (cmd.trim().into(), args.trim().into())
+        })
+        .collect())
+}
+
+/// Tells whether a line of the "list-commands" output is part of the header.
+fn is_list_commands_header(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("The available commands are:") || line.ends_with(':')
+}
+
+/// Parses the output of the "version" command and returns the version of the daemon.
+pub fn version(response: &str) -> Result<(u32, u32, u32, String)> {
+    let invalid = InvalidResponse::new("version", response);
+
+    version_triple(
+        &invalid,
+        response
+            .trim()
+            .strip_prefix("ovs-vswitchd (Open vSwitch) ")
+            .ok_or_else(|| invalid.error("invalid prefix".to_string()))?,
+    )
+}
+
+/// Parses a version made of three numbers and an optional suffix, e.g: "3.1.2" or
+/// "3.1.2-dirty". The suffix is returned as is, or empty.
+pub(crate) fn version_triple(
+    invalid: &InvalidResponse,
+    version: &str,
+) -> Result<(u32, u32, u32, String)> {
+    match version.splitn(4, &['.', '-']).collect::<Vec<&str>>()[..] {
+        [x, y, z] => Ok((
+            invalid.parse(x)?,
+            invalid.parse(y)?,
+            invalid.parse(z)?,
+            String::default(),
+        )),
+        [x, y, z, patch] => Ok((
+            invalid.parse(x)?,
+            invalid.parse(y)?,
+            invalid.parse(z)?,
+            String::from(patch),
+        )),
+        _ => Err(invalid.error("parse error".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_commands_header() {
+        let expected = vec![
+            ("bond/list".to_string(), String::default()),
+            ("bond/show".to_string(), "[port]".to_string()),
+        ];
+        assert_eq!(
+            list_commands("The available commands are:\n  bond/list\n  bond/show  [port]\n")
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            list_commands(
+                "ovn-controller 24.03.1\nAvailable commands:\n  bond/list\n  bond/show  [port]\n"
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            list_commands("  bond/list\n  bond/show  [port]\n").unwrap(),
+            expected
+        );
+        assert_eq!(
+            list_commands("bond/list\nbond/show [port]").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn ovs_duration() {
+        assert_eq!(
+            parse_ovs_duration("0.123s").unwrap(),
+            Some(Duration::from_millis(123))
+        );
+        assert_eq!(
+            parse_ovs_duration("12s").unwrap(),
+            Some(Duration::from_secs(12))
+        );
+        assert_eq!(
+            parse_ovs_duration("5ms").unwrap(),
+            Some(Duration::from_millis(5))
+        );
+        assert_eq!(
+            parse_ovs_duration("1h2m3s").unwrap(),
+            Some(Duration::from_secs(3723))
+        );
+        assert_eq!(
+            parse_ovs_duration("2d1h").unwrap(),
+            Some(Duration::from_secs(2 * 86400 + 3600))
+        );
+        assert_eq!(
+            parse_ovs_duration("1m500ms").unwrap(),
+            Some(Duration::from_millis(60500))
+        );
+        assert_eq!(parse_ovs_duration("never").unwrap(), None);
+
+        assert!(parse_ovs_duration("").is_err());
+        assert!(parse_ovs_duration("12").is_err());
+        assert!(parse_ovs_duration("3w").is_err());
+        // Out of the range of Duration, alone or once summed.
+        assert!(parse_ovs_duration("99999999999999999999s").is_err());
+        assert!(parse_ovs_duration("18446744073709000000s18446744073709000000s").is_err());
+        assert!(matches!(
+            dpctl::flows("in_port(1), packets:0, bytes:0, used:99999999999999999999s, actions:drop"),
+            Err(Error::OvsInvalidResponse { cmd, .. }) if cmd == "dpctl/dump-flows"
+        ));
+        assert!(parse_ovs_duration("a.bs").is_err());
+    }
+
+    #[test]
+    fn parse_version() {
+        assert_eq!(
+            version("ovs-vswitchd (Open vSwitch) 3.1.2\n").unwrap(),
+            (3, 1, 2, String::default())
+        );
+        assert_eq!(
+            version("ovs-vswitchd (Open vSwitch) 3.3.0-47.el9fdp\n").unwrap(),
+            (3, 3, 0, "47.el9fdp".to_string())
+        );
+        assert!(version("ovsdb-server (Open vSwitch) 3.1.2").is_err());
+        assert!(version("ovs-vswitchd (Open vSwitch) 3.x.2").is_err());
+    }
+}
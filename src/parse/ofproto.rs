@@ -0,0 +1,473 @@
+//! Parsers of OpenFlow switch (ofproto) command outputs.
+
+use super::InvalidResponse;
+use crate::{
+    ofproto::{DpifBridge, DpifPort, TraceResult, TraceStep, TraceStepKind, TraceTable},
+    Result,
+};
+
+/// Parses the output of the "ofproto/trace" command.
+///
+/// The bridge sections of all the passes are kept, while the final flow, megaflow and datapath
+/// actions are the ones of the last pass when the trace recirculates. The bridge sections are
+/// also split into table visits and the steps between them.
+pub fn trace(response: &str) -> Result<TraceResult> {
+    let invalid = InvalidResponse::new("ofproto/trace", response);
+
+    let mut trace = TraceResult::default();
+    let mut bridge: Vec<&str> = Vec::new();
+    let mut in_bridge = false;
+    let mut steps = TraceSteps::default();
+    for line in response.lines() {
+        if line.starts_with("bridge(\"") {
+            in_bridge = true;
+        } else if line.starts_with("Final flow:")
+            || line.starts_with("Datapath actions:")
+            || line.starts_with("=====")
+        {
+            in_bridge = false;
+        }
+        if in_bridge {
+            bridge.push(line);
+            steps.line(&mut trace, line, &invalid)?;
+            continue;
+        }
+
+        if let Some(flow) = line.strip_prefix("Flow:") {
+            if trace.flow.is_empty() {
+                trace.flow = flow.trim().to_string();
+            }
+        } else if let Some(flow) = line.strip_prefix("Final flow:") {
+            trace.final_flow = flow.trim().to_string();
+        } else if let Some(flow) = line.strip_prefix("Megaflow:") {
+            trace.megaflow = flow.trim().to_string();
+        } else if let Some(actions) = line.strip_prefix("Datapath actions:") {
+            trace.datapath_actions = actions.trim().to_string();
+        } else if line.starts_with("recirc(") {
+            steps.recirc(line);
+        }
+    }
+    trace.bridge = bridge.join("\n").trim().to_string();
+
+    if trace.flow.is_empty() {
+        return Err(invalid.error("flow not found".to_string()));
+    }
+    if trace.datapath_actions.is_empty() {
+        return Err(invalid.error("datapath actions not found".to_string()));
+    }
+    Ok(trace)
+}
+
+/// State of the split of the bridge sections of a trace into table visits and steps.
+///
+/// Table visits are the `N. rule` lines. OVS prints the table numbers right-aligned on two
+/// characters and indents nested resubmits by 4 more characters, so the column of the dot gives
+/// the nesting depth. Resubmits ending a rule aren't nested: the visit following a rule whose
+/// last action is a resubmit, at the same depth, comes from it.
+#[derive(Default)]
+struct TraceSteps {
+    /// Current bridge.
+    bridge: String,
+    /// Current pass and the header of the recirculation which started it, until its first visit.
+    pass: usize,
+    recirc: Option<String>,
+    /// Column of the dot of each visit, i.e: `2 + 4 * depth`.
+    columns: Vec<usize>,
+}
+
+impl TraceSteps {
+    /// Handles a line of a bridge section.
+    fn line(
+        &mut self,
+        trace: &mut TraceResult,
+        line: &str,
+        invalid: &InvalidResponse,
+    ) -> Result<()> {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if let Some(bridge) = line
+            .strip_prefix("bridge(\"")
+            .and_then(|b| b.strip_suffix("\")"))
+        {
+            self.bridge = bridge.to_string();
+            return Ok(());
+        }
+
+        // Table visits, e.g: " 0. ip, priority 32768".
+        if let Some((table, rule)) = line
+            .split_once(". ")
+            .filter(|(t, _)| !t.is_empty() && t.bytes().all(|b| b.is_ascii_digit()))
+        {
+            let column = indent + table.len();
+            let id = trace.tables.len();
+            if let Some(header) = self.recirc.take() {
+                if let Some(from) = id.checked_sub(1) {
+                    trace.steps.push(TraceStep {
+                        kind: TraceStepKind::Recirc,
+                        from,
+                        to: id,
+                        actions: vec![header],
+                    });
+                }
+            } else if let Some(from) = self.resubmitted_from(trace, column) {
+                trace.steps.push(TraceStep {
+                    kind: TraceStepKind::Resubmit,
+                    from,
+                    to: id,
+                    actions: trace.tables[from].actions.clone(),
+                });
+            }
+            trace.tables.push(TraceTable {
+                bridge: self.bridge.clone(),
+                table: invalid.parse(table)?,
+                rule: rule.to_string(),
+                actions: Vec::new(),
+                pass: self.pass,
+            });
+            self.columns.push(column);
+            return Ok(());
+        }
+
+        // Actions belong to the last visit of the pass whose rule is less indented.
+        if line.is_empty()
+            || line.starts_with("---")
+            || line.starts_with("->")
+            || line.starts_with(">>>>")
+        {
+            return Ok(());
+        }
+        let visit = (0..trace.tables.len())
+            .rev()
+            .take_while(|&id| trace.tables[id].pass == self.pass)
+            .find(|&id| self.columns[id] < indent);
+        if let Some(id) = visit {
+            trace.tables[id].actions.push(line.to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns the visit of the current pass a visit whose dot is at the given column was
+    /// resubmitted from, if any.
+    fn resubmitted_from(&self, trace: &TraceResult, column: usize) -> Option<usize> {
+        let mut visits = (0..trace.tables.len())
+            .rev()
+            .take_while(|&id| trace.tables[id].pass == self.pass);
+        let id = visits.clone().find(|&id| self.columns[id] <= column)?;
+        if self.columns[id] < column {
+            // Nested resubmit.
+            return Some(id);
+        }
+        // Same depth: resubmit ending the previous rule, or a nested resubmit from its parent.
+        let tail = trace.tables[id]
+            .actions
+            .last()
+            .is_some_and(|a| a.starts_with("resubmit") || a.starts_with("goto_table"));
+        if tail {
+            Some(id)
+        } else {
+            visits.find(|&id| self.columns[id] < column)
+        }
+    }
+
+    /// Handles the header of a recirculation, starting a new pass.
+    fn recirc(&mut self, header: &str) {
+        self.pass += 1;
+        self.recirc = Some(header.trim().to_string());
+    }
+}
+
+/// Parses the output of the "dpif/show" command and returns the bridges.
+///
+/// Datapaths are the non-indented lines, bridges and their ports are indented below them: the
+/// indentation is tracked to tell them apart. The flow statistics older versions print below each
+/// bridge are supported.
+pub fn dpif_show(response: &str) -> Result<Vec<DpifBridge>> {
+    let invalid = InvalidResponse::new("dpif/show", response);
+    let invalid_line = |line: &str| invalid.error(format!("invalid line: {line}"));
+
+    let mut datapath_type = None;
+    let mut bridges: Vec<DpifBridge> = Vec::new();
+    // Indentation of the bridge lines of the current datapath.
+    let mut bridge_indent = None;
+    for line in response.lines().filter(|l| !l.trim().is_empty()) {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+
+        // Datapaths, e.g: "system@ovs-system: hit:14 missed:2".
+        if indent == 0 {
+            let (dp, _) = line.split_once(':').ok_or_else(|| invalid_line(line))?;
+            let (type_, _) = dp.split_once('@').ok_or_else(|| invalid_line(line))?;
+            datapath_type = Some(type_.to_string());
+            bridge_indent = None;
+            continue;
+        }
+        let datapath_type = datapath_type.as_ref().ok_or_else(|| invalid_line(line))?;
+
+        // Bridges, e.g: "br0:" or, in older versions, "br0: hit:0 missed:0".
+        if bridge_indent.is_none_or(|bridge_indent| indent <= bridge_indent) {
+            // Older versions print the datapath flow statistics at the bridge level.
+            if line.starts_with("flows:") {
+                continue;
+            }
+            let (bridge, _) = line.split_once(':').ok_or_else(|| invalid_line(line))?;
+            bridges.push(DpifBridge {
+                datapath_type: datapath_type.clone(),
+                bridge: bridge.to_string(),
+                ..Default::default()
+            });
+            bridge_indent = Some(indent);
+            continue;
+        }
+        let bridge = bridges.last_mut().ok_or_else(|| invalid_line(line))?;
+
+        // Flow statistics of older versions, e.g: "flows: cur: 0, avg: 0, max: 0, ...".
+        if let Some(flows) = line.strip_prefix("flows:") {
+            let count = flows
+                .trim()
+                .strip_prefix("cur:")
+                .and_then(|f| f.split(',').next())
+                .ok_or_else(|| invalid_line(line))?;
+            bridge.flow_count = Some(invalid.parse(count)?);
+            continue;
+        }
+
+        // Ports, e.g: "br0 65534/1: (internal)".
+        let (port, type_) = line.split_once(": ").ok_or_else(|| invalid_line(line))?;
+        let (name, numbers) = port.rsplit_once(' ').ok_or_else(|| invalid_line(line))?;
+        let (port_no, dp_port_no) = numbers.split_once('/').ok_or_else(|| invalid_line(line))?;
+        bridge.ports.push(DpifPort {
+            name: name.to_string(),
+            port_no: invalid.parse(port_no)?,
+            dp_port_no: match dp_port_no {
+                "none" => None,
+                dp_port_no => Some(invalid.parse(dp_port_no)?),
+            },
+            type_: type_
+                .trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .to_string(),
+        });
+    }
+
+    Ok(bridges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_trace_resubmits() {
+        let res = trace(
+            "Flow: in_port=1,vlan_tci=0x0000,dl_src=00:00:00:00:00:01,dl_dst=00:00:00:00:00:02
+
+bridge(\"br0\")
+-------------
+ 0. in_port=1, priority 32768
+    resubmit(,1)
+ 1. priority 99
+    set_field:0x1->reg0
+    resubmit(,10)
+    10. No match.
+            drop
+    resubmit(,2)
+ 2. priority 0
+    output:2
+     -> output to kernel tunnel
+
+Final flow: unchanged
+Megaflow: recirc_id=0,eth,in_port=1,dl_type=0x0000
+Datapath actions: 2
+",
+        )
+        .unwrap();
+
+        let tables: Vec<(u32, &str, usize)> = res
+            .tables
+            .iter()
+            .map(|t| (t.table, t.rule.as_str(), t.actions.len()))
+            .collect();
+        assert_eq!(
+            tables,
+            vec![
+                (0, "in_port=1, priority 32768", 1),
+                (1, "priority 99", 3),
+                (10, "No match.", 1),
+                (2, "priority 0", 1),
+            ]
+        );
+        let steps: Vec<(usize, usize, &str)> = res
+            .steps
+            .iter()
+            .map(|s| (s.from, s.to, s.actions.last().unwrap().as_str()))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (0, 1, "resubmit(,1)"),
+                (1, 2, "resubmit(,10)"),
+                (1, 3, "resubmit(,2)")
+            ]
+        );
+        assert!(res.steps.iter().all(|s| s.kind == TraceStepKind::Resubmit));
+    }
+
+    #[test]
+    fn parse_dpif_show() {
+        let port = |name: &str, port_no, dp_port_no, type_: &str| DpifPort {
+            name: name.to_string(),
+            port_no,
+            dp_port_no,
+            type_: type_.to_string(),
+        };
+
+        assert_eq!(
+            dpif_show(
+                "system@ovs-system: hit:14 missed:2\n  \
+                   br0:\n    \
+                     br0 65534/1: (internal)\n    \
+                     eth0 1/2: (system)\n    \
+                     vxlan0 2/3: (vxlan: remote_ip=10.0.0.1)\n  \
+                   br1:\n    \
+                     br1 65534/none: (internal)\n\
+                 netdev@ovs-netdev: hit:0 missed:0\n  \
+                   br2:\n    \
+                     br2 65534/1: (tap)\n",
+            )
+            .unwrap(),
+            vec![
+                DpifBridge {
+                    datapath_type: "system".to_string(),
+                    bridge: "br0".to_string(),
+                    flow_count: None,
+                    ports: vec![
+                        port("br0", 65534, Some(1), "internal"),
+                        port("eth0", 1, Some(2), "system"),
+                        port("vxlan0", 2, Some(3), "vxlan: remote_ip=10.0.0.1"),
+                    ],
+                },
+                DpifBridge {
+                    datapath_type: "system".to_string(),
+                    bridge: "br1".to_string(),
+                    flow_count: None,
+                    ports: vec![port("br1", 65534, None, "internal")],
+                },
+                DpifBridge {
+                    datapath_type: "netdev".to_string(),
+                    bridge: "br2".to_string(),
+                    flow_count: None,
+                    ports: vec![port("br2", 65534, Some(1), "tap")],
+                },
+            ]
+        );
+
+        // Older versions, with tabs and flow statistics.
+        assert_eq!(
+            dpif_show(
+                "system@ovs-system: hit:0 missed:0\n\
+                 \tflows: cur: 0, avg: 0, max: 0, life span: 0ms\n\
+                 \tbr0: hit:0 missed:0\n\
+                 \t\tflows: cur: 3, avg: 2, max: 4, life span: 10ms\n\
+                 \t\tbr0 65534/1: (internal)\n",
+            )
+            .unwrap(),
+            vec![DpifBridge {
+                datapath_type: "system".to_string(),
+                bridge: "br0".to_string(),
+                flow_count: Some(3),
+                ports: vec![port("br0", 65534, Some(1), "internal")],
+            }]
+        );
+
+        assert!(dpif_show("  br0:\n").is_err());
+        assert!(
+            dpif_show("system@ovs-system: hit:0 missed:0\n  br0:\n    br0 x/1: (internal)\n")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_trace() {
+        let res = trace(
+            "Flow: icmp,in_port=1,vlan_tci=0x0000,nw_src=10.0.0.1,nw_dst=10.0.0.2
+
+bridge(\"br0\")
+-------------
+ 0. ip, priority 32768
+    ct(table=1)
+    drop
+     -> A clone of the packet is forked to recirculate. The forked pipeline will be resumed at table 1.
+
+Final flow: unchanged
+Megaflow: recirc_id=0,eth,ip,in_port=1,nw_frag=no
+Datapath actions: ct,recirc(0x1)
+
+===============================================================================
+recirc(0x1) - resume conntrack with default ct_state=trk|new (use --ct-next to customize)
+===============================================================================
+
+Flow: recirc_id=0x1,ct_state=new|trk,icmp,in_port=1
+
+bridge(\"br0\")
+-------------
+    thaw
+        Resuming from table 1
+ 1. priority 0
+    output:2
+
+Final flow: recirc_id=0x1,ct_state=new|trk,icmp,in_port=1
+Megaflow: recirc_id=0x1,eth,ip,in_port=1,nw_frag=no
+Datapath actions: 2
+",
+        )
+        .unwrap();
+        assert_eq!(
+            res.flow,
+            "icmp,in_port=1,vlan_tci=0x0000,nw_src=10.0.0.1,nw_dst=10.0.0.2"
+        );
+        assert!(res
+            .bridge
+            .starts_with("bridge(\"br0\")\n-------------\n 0. ip"));
+        assert!(res.bridge.ends_with(" 1. priority 0\n    output:2"));
+        assert_eq!(
+            res.final_flow,
+            "recirc_id=0x1,ct_state=new|trk,icmp,in_port=1"
+        );
+        assert_eq!(res.megaflow, "recirc_id=0x1,eth,ip,in_port=1,nw_frag=no");
+        assert_eq!(res.datapath_actions, "2");
+        assert_eq!(
+            res.tables,
+            vec![
+                TraceTable {
+                    bridge: "br0".to_string(),
+                    table: 0,
+                    rule: "ip, priority 32768".to_string(),
+                    actions: vec!["ct(table=1)".to_string(), "drop".to_string()],
+                    pass: 0,
+                },
+                TraceTable {
+                    bridge: "br0".to_string(),
+                    table: 1,
+                    rule: "priority 0".to_string(),
+                    actions: vec!["output:2".to_string()],
+                    pass: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            res.steps,
+            vec![TraceStep {
+                kind: TraceStepKind::Recirc,
+                from: 0,
+                to: 1,
+                actions: vec![
+                    "recirc(0x1) - resume conntrack with default ct_state=trk|new (use --ct-next to customize)"
+                        .to_string()
+                ],
+            }]
+        );
+
+        assert!(trace("ovs-vswitchd: br1: unknown bridge\n").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! Parsers of OVN command outputs.
+
+use std::collections::HashMap;
+
+use super::InvalidResponse;
+use crate::{
+    ovn::{EngineStat, LflowCacheStats},
+    Result,
+};
+
+/// Parses the output of ovn-controller's "ct-zone-list" command and returns the conntrack zone
+/// assigned to each logical port.
+///
+/// ```
+/// let zones = ovs_unixctl::parse::ovn::ct_zone_list("lsp-vm1 1\nlsp-vm2 2\n").unwrap();
+/// assert_eq!(zones["lsp-vm2"], 2);
+/// ```
+pub fn ct_zone_list(response: &str) -> Result<HashMap<String, u16>> {
+    let invalid = InvalidResponse::new("ct-zone-list", response);
+
+    response
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            let (name, zone) = l
+                .trim()
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| invalid.error(format!("invalid line: {l}")))?;
+            Ok((name.trim().to_string(), invalid.parse(zone)?))
+        })
+        .collect()
+}
+
+/// Parses the output of ovn-controller's "inc-engine/show-stats" command and returns the
+/// statistics of each incremental processing engine node.
+pub fn inc_engine_stats(response: &str) -> Result<HashMap<String, EngineStat>> {
+    let invalid = InvalidResponse::new("inc-engine/show-stats", response);
+
+    let mut nodes = HashMap::new();
+    let mut current: Option<(String, EngineStat)> = None;
+    for line in response.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(node) = line.strip_prefix("Node:") {
+            if let Some((name, stat)) = current.take() {
+                nodes.insert(name, stat);
+            }
+            current = Some((node.trim().to_string(), EngineStat::default()));
+            continue;
+        }
+
+        let (_, stat) = current
+            .as_mut()
+            .ok_or_else(|| invalid.error(format!("counter outside of a node: {line}")))?;
+        let (key, val) = line
+            .trim_start_matches('-')
+            .split_once(':')
+            .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+        let val: u64 = invalid.parse(val)?;
+        match key.trim() {
+            "recompute" => stat.recompute = val,
+            "compute" => stat.compute = val,
+            // Newer versions of OVN renamed "abort" into "cancel".
+            "abort" | "cancel" => stat.abort = val,
+            // Ignore counters we don't know about.
+            _ => (),
+        }
+    }
+    if let Some((name, stat)) = current {
+        nodes.insert(name, stat);
+    }
+
+    Ok(nodes)
+}
+
+/// Parses the output of ovn-controller's "lflow-cache/show-stats" command.
+pub fn lflow_cache_stats(response: &str) -> Result<LflowCacheStats> {
+    let invalid = InvalidResponse::new("lflow-cache/show-stats", response);
+
+    let mut stats = LflowCacheStats::default();
+    for line in response.lines().filter(|l| !l.trim().is_empty()) {
+        let (key, val) = line
+            .split_once(':')
+            .ok_or_else(|| invalid.error(format!("invalid line: {line}")))?;
+        match key.trim() {
+            "Enabled" => stats.enabled = invalid.parse(val)?,
+            "total" => stats.entries = invalid.parse(val)?,
+            "Mem usage (KB)" => stats.mem_usage_kb = invalid.parse(val)?,
+            _ => (),
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ct_zone_list() {
+        let zones = ct_zone_list(
+            "a4de9b4a-13a3-4d5f-a1a5-7a3ad42c2b2a_dnat 3\n\
+             lsp-vm1 1\n\
+             lsp-vm2 2\n",
+        )
+        .unwrap();
+        assert_eq!(zones.len(), 3);
+        assert_eq!(zones["a4de9b4a-13a3-4d5f-a1a5-7a3ad42c2b2a_dnat"], 3);
+        assert_eq!(zones["lsp-vm1"], 1);
+        assert_eq!(zones["lsp-vm2"], 2);
+
+        assert!(ct_zone_list("").unwrap().is_empty());
+        assert!(ct_zone_list("lsp-vm1 foo").is_err());
+    }
+
+    #[test]
+    fn parse_inc_engine_stats() {
+        let stats = inc_engine_stats(
+            "Node: SB_sb_global\n\
+             - recompute:            2\n\
+             - compute:              0\n\
+             - abort:                0\n\
+             Node: lflow_output\n\
+             - recompute:            5\n\
+             - compute:             42\n\
+             - cancel:               1\n",
+        )
+        .unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats["SB_sb_global"],
+            EngineStat {
+                recompute: 2,
+                compute: 0,
+                abort: 0,
+            }
+        );
+        assert_eq!(
+            stats["lflow_output"],
+            EngineStat {
+                recompute: 5,
+                compute: 42,
+                abort: 1,
+            }
+        );
+
+        assert!(inc_engine_stats("- recompute: 1").is_err());
+        assert!(inc_engine_stats("Node: foo\n- compute: bar").is_err());
+    }
+
+    #[test]
+    fn parse_lflow_cache_stats() {
+        let stats = lflow_cache_stats(
+            "Enabled: true\n\
+             high-watermark  : 12\n\
+             total           : 10\n\
+             cache-expr      : 4\n\
+             cache-matches   : 6\n\
+             trim count      : 0\n\
+             Mem usage (KB)  : 27\n",
+        )
+        .unwrap();
+        assert_eq!(
+            stats,
+            LflowCacheStats {
+                enabled: true,
+                entries: 10,
+                mem_usage_kb: 27,
+            }
+        );
+
+        assert!(!lflow_cache_stats("Enabled: false").unwrap().enabled);
+        assert!(lflow_cache_stats("total: many").is_err());
+    }
+}
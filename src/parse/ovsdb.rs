@@ -0,0 +1,47 @@
+//! Parsers of OVSDB server command outputs.
+
+use super::{version_triple, InvalidResponse};
+use crate::Result;
+
+/// Parses the output of the "ovsdb-server/get-active-ovsdb-server" command and returns the
+/// configured active server remote, or `None` if none is configured.
+pub fn active_server(response: &str) -> Result<Option<String>> {
+    Ok(match response.trim() {
+        "" | "none" => None,
+        remote => Some(remote.to_string()),
+    })
+}
+
+/// Parses the version of a database schema, as found in the "version" member of the schema
+/// returned by the OVSDB "get_schema" method, e.g: "8.3.0".
+pub fn schema_version(version: &str) -> Result<(u32, u32, u32)> {
+    let invalid = InvalidResponse::new("get_schema", version);
+    match version_triple(&invalid, version.trim())? {
+        (x, y, z, suffix) if suffix.is_empty() => Ok((x, y, z)),
+        _ => Err(invalid.error("unexpected version suffix".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_active_server() {
+        assert_eq!(
+            active_server("tcp:192.168.0.10:6641\n").unwrap().as_deref(),
+            Some("tcp:192.168.0.10:6641")
+        );
+        assert_eq!(active_server("").unwrap(), None);
+        assert_eq!(active_server("none\n").unwrap(), None);
+    }
+
+    #[test]
+    fn parse_schema_version() {
+        assert_eq!(schema_version("8.3.0").unwrap(), (8, 3, 0));
+        assert_eq!(schema_version("5.12.1\n").unwrap(), (5, 12, 1));
+        assert!(schema_version("8.3").is_err());
+        assert!(schema_version("8.3.0-dirty").is_err());
+        assert!(schema_version("8.x.0").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Parsers of userspace routing table (ovs/route) command outputs.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::InvalidResponse;
+use crate::{route::RouteResult, Result};
+
+/// Parses the output of the "ovs/route/lookup" command and returns the route found, or `None`
+/// if there is no route to the destination.
+pub fn lookup(response: &str) -> Result<Option<RouteResult>> {
+    let invalid = InvalidResponse::new("ovs/route/lookup", response);
+    if response.trim() == "Not found" {
+        return Ok(None);
+    }
+
+    let addr = |val: &str| -> Result<Option<IpAddr>> {
+        let addr: IpAddr = invalid.parse(val)?;
+        // OVS prints IPv4 addresses mapped in IPv6 ones; unmap them.
+        let addr = match addr {
+            IpAddr::V6(v6) => v6
+                .to_ipv4_mapped()
+                .map(IpAddr::V4)
+                .unwrap_or(IpAddr::V6(v6)),
+            v4 => v4,
+        };
+        Ok(match addr {
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED) | IpAddr::V6(Ipv6Addr::UNSPECIFIED) => None,
+            addr => Some(addr),
+        })
+    };
+
+    let (mut src, mut gateway, mut dev) = (None, None, None);
+    for line in response.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        match line.split_once(char::is_whitespace) {
+            Some(("src", val)) => src = addr(val)?,
+            Some(("gateway", val)) => gateway = addr(val)?,
+            Some(("dev", val)) => dev = Some(val.trim().to_string()),
+            _ => return Err(invalid.error(format!("invalid line: {line}"))),
+        }
+    }
+
+    Ok(Some(RouteResult {
+        src,
+        gateway,
+        dev: dev.ok_or_else(|| invalid.error("dev not found".to_string()))?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lookup() {
+        assert_eq!(
+            lookup("src 192.168.1.10\ngateway ::\ndev eth0\n").unwrap(),
+            Some(RouteResult {
+                src: Some("192.168.1.10".parse().unwrap()),
+                gateway: None,
+                dev: "eth0".to_string(),
+            })
+        );
+        assert_eq!(
+            lookup("src ::ffff:10.0.0.2\ngateway ::ffff:10.0.0.1\ndev br-ex\n").unwrap(),
+            Some(RouteResult {
+                src: Some("10.0.0.2".parse().unwrap()),
+                gateway: Some("10.0.0.1".parse().unwrap()),
+                dev: "br-ex".to_string(),
+            })
+        );
+        assert_eq!(
+            lookup("src 2001:db8::2\ngateway fe80::1\ndev br-ex\n").unwrap(),
+            Some(RouteResult {
+                src: Some("2001:db8::2".parse().unwrap()),
+                gateway: Some("fe80::1".parse().unwrap()),
+                dev: "br-ex".to_string(),
+            })
+        );
+        assert_eq!(lookup("Not found").unwrap(), None);
+
+        assert!(lookup("src 10.0.0.2\ngateway 10.0.0.1\n").is_err());
+        assert!(lookup("src foo\ndev eth0\n").is_err());
+    }
+}
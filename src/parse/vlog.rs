@@ -0,0 +1,69 @@
+//! Parsers of logging (vlog) command outputs.
+
+use super::InvalidResponse;
+use crate::{vlog::VlogModule, Result};
+
+/// Parses the output of the "vlog/list" command and returns the logging modules and their log
+/// levels.
+///
+/// The header lines, naming the destinations, are skipped.
+pub fn list(response: &str) -> Result<Vec<VlogModule>> {
+    let invalid = InvalidResponse::new("vlog/list", response);
+
+    response
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with(char::is_whitespace))
+        .map(
+            |line| match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [name, console, syslog, file] => Ok(VlogModule {
+                    name: name.to_string(),
+                    console: invalid.parse(console)?,
+                    syslog: invalid.parse(syslog)?,
+                    file: invalid.parse(file)?,
+                }),
+                _ => Err(invalid.error(format!("invalid line: {line}"))),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{vlog::VlogLevel, Error};
+
+    #[test]
+    fn parse_list() {
+        let modules = list(
+            "                 console    syslog    file
+                 -------    ------    ------
+backtrace          OFF       ERR       INFO
+unixctl            OFF       ERR       DBG
+",
+        )
+        .unwrap();
+        assert_eq!(
+            modules,
+            vec![
+                VlogModule {
+                    name: "backtrace".to_string(),
+                    console: VlogLevel::Off,
+                    syslog: VlogLevel::Err,
+                    file: VlogLevel::Info,
+                },
+                VlogModule {
+                    name: "unixctl".to_string(),
+                    console: VlogLevel::Off,
+                    syslog: VlogLevel::Err,
+                    file: VlogLevel::Dbg,
+                },
+            ]
+        );
+
+        assert!(list("unixctl OFF ERR").is_err());
+        assert!(matches!(
+            list("unixctl OFF ERR LOUD"),
+            Err(Error::OvsInvalidResponse { cmd, .. }) if cmd == "vlog/list"
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+//! Userspace routing table (ovs/route) helpers.
+
+use std::net::IpAddr;
+
+use crate::{error::Error, parse, OvsUnixCtl, Result};
+
+/// The result of a route lookup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RouteResult {
+    /// Preferred source address, if any.
+    pub src: Option<IpAddr>,
+    /// Gateway to reach the destination, `None` for directly connected routes.
+    pub gateway: Option<IpAddr>,
+    /// Output device.
+    pub dev: String,
+}
+
+impl OvsUnixCtl {
+    /// Runs the "ovs/route/lookup" command and returns the route used to reach the given
+    /// destination, or `None` if there is no route to it.
+    pub fn route_lookup(&mut self, dst: IpAddr) -> Result<Option<RouteResult>> {
+        match self.run("ovs/route/lookup", Some(&[&dst.to_string()])) {
+            Ok(response) => parse::route::lookup(&response.unwrap_or_default()),
+            Err(Error::Command { error, .. }) if error.trim() == "Not found" => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeServer;
+
+    #[test]
+    fn route_lookup_not_found() {
+        let server = FakeServer::new("route_lookup", |_, params| match params[0].as_str() {
+            "10.0.0.1" => Ok("src 10.0.0.2\ngateway ::\ndev br0\n".to_string()),
+            _ => Err("Not found".to_string()),
+        });
+        let mut ovs = OvsUnixCtl::unix(server.path(), None).unwrap();
+        assert_eq!(
+            ovs.route_lookup("10.0.0.1".parse().unwrap())
+                .unwrap()
+                .unwrap()
+                .dev,
+            "br0"
+        );
+        assert_eq!(
+            ovs.route_lookup("2001:db8::1".parse().unwrap()).unwrap(),
+            None
+        );
+        drop(ovs);
+
+        assert_eq!(
+            server.requests(),
+            vec![
+                ("ovs/route/lookup".to_string(), vec!["10.0.0.1".to_string()]),
+                (
+                    "ovs/route/lookup".to_string(),
+                    vec!["2001:db8::1".to_string()]
+                ),
+            ]
+        );
+    }
+}
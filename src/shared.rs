@@ -0,0 +1,87 @@
+//! Thread-safe OvsUnixCtl handle.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::{OvsUnixCtl, OvsVersion, Result};
+
+/// A cloneable, thread-safe handle to an [`OvsUnixCtl`].
+///
+/// All clones share the same connection: commands are run one at a time, callers from other
+/// threads waiting for the running one to complete. Applications issuing many concurrent
+/// commands should rather use several [`OvsUnixCtl`] instances, each having its own connection.
+#[derive(Clone, Debug)]
+pub struct SharedOvsUnixCtl {
+    inner: Arc<Mutex<OvsUnixCtl>>,
+}
+
+impl SharedOvsUnixCtl {
+    /// Creates a new shared handle from an existing OvsUnixCtl.
+    pub fn new(unixctl: OvsUnixCtl) -> SharedOvsUnixCtl {
+        SharedOvsUnixCtl {
+            inner: Arc::new(Mutex::new(unixctl)),
+        }
+    }
+
+    /// Locks the underlying OvsUnixCtl, e.g: to run several commands in a row or to use a helper
+    /// not exposed by this handle.
+    pub fn lock(&self) -> MutexGuard<'_, OvsUnixCtl> {
+        // A panic while holding the lock doesn't leave the OvsUnixCtl in an unusable state.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// See [`OvsUnixCtl::list_commands`].
+    pub fn list_commands(&self) -> Result<Vec<(String, String)>> {
+        self.lock().list_commands()
+    }
+
+    /// See [`OvsUnixCtl::version`].
+    pub fn version(&self) -> Result<OvsVersion> {
+        self.lock().version()
+    }
+
+    /// See [`OvsUnixCtl::run`].
+    pub fn run(&self, cmd: &str, params: Option<&[&str]>) -> Result<Option<String>> {
+        self.lock().run(cmd, params)
+    }
+}
+
+impl From<OvsUnixCtl> for SharedOvsUnixCtl {
+    fn from(unixctl: OvsUnixCtl) -> SharedOvsUnixCtl {
+        SharedOvsUnixCtl::new(unixctl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::testing::FakeServer;
+
+    #[test]
+    fn shared_threads() {
+        let server = FakeServer::new("shared_threads", |cmd, params| {
+            Ok(format!("{cmd} {}", params.join(" ")))
+        });
+        let shared = SharedOvsUnixCtl::new(OvsUnixCtl::unix(server.path(), None).unwrap());
+
+        let threads: Vec<_> = (0..2)
+            .map(|t| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    for i in 0..10 {
+                        let arg = format!("{t}-{i}");
+                        assert_eq!(
+                            shared.run("echo", Some(&[&arg])).unwrap(),
+                            Some(format!("echo {arg}"))
+                        );
+                    }
+                })
+            })
+            .collect();
+        threads.into_iter().for_each(|t| t.join().unwrap());
+        drop(shared);
+
+        assert_eq!(server.requests().len(), 20);
+    }
+}
@@ -0,0 +1,205 @@
+//! Synchronous jsonrpc transport over TCP.
+
+use std::{
+    fmt, io,
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    os::unix::io::{AsRawFd, RawFd},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::*,
+    jsonrpc::{recv_json_pending, send_json, try_recv_json, JsonStream, JsonStreamClient},
+    Result,
+};
+
+/// TCP transport.
+#[derive(Debug)]
+pub(crate) struct TcpJsonStream {
+    /// The socket, buffered for reading. Bytes read past the end of a message are kept for the
+    /// next one.
+    sock: io::BufReader<TcpStream>,
+    /// Bytes of a message partially received by a non-blocking receive.
+    pending: Vec<u8>,
+    sent: u64,
+    received: u64,
+}
+
+impl TcpJsonStream {
+    pub(crate) fn new(sock: TcpStream) -> TcpJsonStream {
+        TcpJsonStream {
+            sock: io::BufReader::new(sock),
+            pending: Vec::new(),
+            sent: 0,
+            received: 0,
+        }
+    }
+}
+
+impl JsonStream for TcpJsonStream {
+    fn send<M: Serialize>(&mut self, msg: M) -> Result<()> {
+        send_json(self.sock.get_mut(), msg, &mut self.sent)
+    }
+
+    fn recv<R>(&mut self) -> Result<R>
+    where
+        R: for<'a> Deserialize<'a>,
+    {
+        recv_json_pending(&mut self.sock, &mut self.pending, &mut self.received)
+    }
+
+    fn bytes(&self) -> (u64, u64) {
+        (self.sent, self.received)
+    }
+
+    fn timeout(&self) -> Result<Option<Duration>> {
+        Ok(self.sock.get_ref().read_timeout()?)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.sock.get_ref().set_read_timeout(timeout)?;
+        self.sock.get_ref().set_write_timeout(timeout)?;
+        Ok(())
+    }
+
+    fn try_recv<R>(&mut self) -> Result<Option<R>>
+    where
+        R: for<'a> Deserialize<'a>,
+    {
+        self.sock.get_ref().set_nonblocking(true)?;
+        let res = try_recv_json(&mut self.sock, &mut self.pending, &mut self.received);
+        self.sock.get_ref().set_nonblocking(false)?;
+        res
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        Some(self.sock.get_ref().as_raw_fd())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TcpJsonStreamClient {
+    /// The addresses to connect to, tried in order.
+    addrs: Vec<SocketAddr>,
+    /// The read and write timeout to use.
+    timeout: Option<Duration>,
+}
+
+impl TcpJsonStreamClient {
+    /// Creates a new [`TcpJsonStreamClient`] without timeouts to use. The address is resolved
+    /// once, here.
+    pub(crate) fn new<A: ToSocketAddrs>(addr: A) -> Result<TcpJsonStreamClient> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(Error::SocketNotFound(
+                "address resolved to nothing".to_string(),
+            ));
+        }
+        Ok(TcpJsonStreamClient {
+            addrs,
+            timeout: None,
+        })
+    }
+
+    /// Sets the timeout.
+    pub(crate) fn timeout(mut self, timeout: Duration) -> TcpJsonStreamClient {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl JsonStreamClient for TcpJsonStreamClient {
+    type Stream = TcpJsonStream;
+
+    fn connect(&mut self) -> Result<TcpJsonStream> {
+        let sock = match self.timeout {
+            // connect_timeout only accepts a single address, try them one by one.
+            Some(timeout) => {
+                let mut res = Err(std::io::ErrorKind::NotConnected.into());
+                for addr in &self.addrs {
+                    res = TcpStream::connect_timeout(addr, timeout);
+                    if res.is_ok() {
+                        break;
+                    }
+                }
+                res
+            }
+            None => TcpStream::connect(&self.addrs[..]),
+        }
+        .map_err(Error::Socket)?;
+        sock.set_read_timeout(self.timeout).map_err(Error::Socket)?;
+        sock.set_write_timeout(self.timeout)
+            .map_err(Error::Socket)?;
+        Ok(TcpJsonStream::new(sock))
+    }
+}
+
+impl fmt::Display for TcpJsonStreamClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        // There is at least one address, see new().
+        write!(f, "tcp://{}", self.addrs[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, net::TcpListener, thread};
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::jsonrpc;
+
+    #[test]
+    fn ping_pong() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let server_thread = thread::spawn(move || {
+            let (sock, _) = server.accept().unwrap();
+            let mut stream = TcpJsonStream::new(sock);
+            for _ in 0..2 {
+                let request: Value = stream.recv().unwrap();
+                assert_eq!(request["method"], "ping");
+                stream
+                    .send(json!({"result": "pong", "error": null, "id": request["id"]}))
+                    .unwrap();
+            }
+        });
+
+        let stream_client = TcpJsonStreamClient::new(addr)
+            .unwrap()
+            .timeout(Duration::from_secs(2));
+        assert_eq!(format!("{}", stream_client), format!("tcp://{addr}"));
+
+        let mut client = jsonrpc::Client::new(stream_client).unwrap();
+        for _ in 0..2 {
+            let response: jsonrpc::Response<String> = client.call("ping").unwrap();
+            assert_eq!(response.result.unwrap(), "pong");
+        }
+        server_thread.join().unwrap();
+    }
+
+    #[test]
+    fn connection_refused() {
+        // Get a free port, nobody listens on it once the listener is dropped.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        for timeout in [None, Some(Duration::from_secs(1))] {
+            let mut stream_client = TcpJsonStreamClient::new(addr).unwrap();
+            if let Some(timeout) = timeout {
+                stream_client = stream_client.timeout(timeout);
+            }
+            assert!(matches!(
+                jsonrpc::Client::new(stream_client),
+                Err(Error::SocketIo { peer, source })
+                    if peer == format!("tcp://{addr}")
+                        && source.kind() == io::ErrorKind::ConnectionRefused
+            ));
+        }
+    }
+}
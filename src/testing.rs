@@ -0,0 +1,150 @@
+//! Test utilities.
+
+use std::{
+    env, fs,
+    os::unix::net::UnixListener,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use serde_json::{json, Deserializer, Value};
+
+/// A request received by the [`FakeServer`]: method and parameters.
+pub(crate) type Recorded = (String, Vec<String>);
+
+/// A fake unixctl server.
+///
+/// It listens on a Unix socket, accepts connections one at a time and answers each received
+/// message using the provided handler. Received requests are recorded so tests can check what was
+/// sent.
+pub(crate) struct FakeServer {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Vec<Vec<Recorded>>>>,
+}
+
+impl FakeServer {
+    /// Creates a new fake server. The handler gets the method and parameters of each request and
+    /// returns either the result or the error to send back.
+    pub(crate) fn new<F>(name: &str, mut handler: F) -> FakeServer
+    where
+        F: FnMut(&str, &[String]) -> Result<String, String> + Send + 'static,
+    {
+        FakeServer::raw(name, move |msg| {
+            let (method, params) = parse_request(msg);
+            let (result, error) = match handler(&method, &params) {
+                Ok(result) => (Some(result), None),
+                Err(error) => (None, Some(error)),
+            };
+            vec![json!({"result": result, "error": error, "id": msg["id"]})]
+        })
+    }
+
+    /// Creates a new fake server with a raw handler. The handler gets each JSON message received
+    /// (requests but also any other message the client sends) and returns the list of messages to
+    /// send back.
+    pub(crate) fn raw<F>(name: &str, mut handler: F) -> FakeServer
+    where
+        F: FnMut(&Value) -> Vec<Value> + Send + 'static,
+    {
+        let path = env::temp_dir().join(format!("ovs-unixctl-fake-{}-{name}.ctl", process::id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).expect("cannot bind fake server socket");
+
+        listener
+            .set_nonblocking(true)
+            .expect("cannot set fake server socket non-blocking");
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            let mut connections = Vec::new();
+            loop {
+                let sock = match listener.accept() {
+                    Ok((sock, _)) => sock,
+                    Err(_) if thread_stop.load(Ordering::Relaxed) => break,
+                    Err(_) => {
+                        thread::sleep(Duration::from_millis(10));
+                        continue;
+                    }
+                };
+                sock.set_nonblocking(false)
+                    .expect("cannot set connection blocking");
+
+                let mut recorded = Vec::new();
+                'conn: for msg in Deserializer::from_reader(&sock).into_iter::<Value>() {
+                    let Ok(msg) = msg else {
+                        break;
+                    };
+                    if msg.get("method").is_some() {
+                        recorded.push(parse_request(&msg));
+                    }
+                    for reply in handler(&msg) {
+                        if serde_json::to_writer(&sock, &reply).is_err() {
+                            break 'conn;
+                        }
+                    }
+                }
+                connections.push(recorded);
+            }
+            connections
+        });
+
+        FakeServer {
+            path,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Path of the socket the server listens on.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits for the clients to disconnect and returns the requests the server received.
+    pub(crate) fn requests(self) -> Vec<Recorded> {
+        self.requests_per_connection().concat()
+    }
+
+    /// Waits for the clients to disconnect and returns the requests the server received, grouped
+    /// by connection.
+    pub(crate) fn requests_per_connection(mut self) -> Vec<Vec<Recorded>> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread
+            .take()
+            .expect("requests already collected")
+            .join()
+            .expect("fake server panicked")
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Extracts the method and parameters of a request.
+fn parse_request(msg: &Value) -> Recorded {
+    let method = msg["method"].as_str().unwrap_or_default().to_string();
+    let params = msg["params"]
+        .as_array()
+        .map(|params| {
+            params
+                .iter()
+                .map(|p| match p.as_str() {
+                    Some(p) => p.to_string(),
+                    None => p.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    (method, params)
+}
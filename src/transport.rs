@@ -0,0 +1,241 @@
+//! Transports usable by [`OvsUnixCtl`](crate::OvsUnixCtl).
+//!
+//! Besides the built-in Unix socket and TCP transports, any transport can be used by implementing
+//! [`CustomTransport`], e.g: a mock replaying canned responses in tests.
+
+use std::{fmt, io, os::unix::io::RawFd, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    jsonrpc::{JsonStream, JsonStreamClient},
+    tcp::{TcpJsonStream, TcpJsonStreamClient},
+    unix::{UnixJsonStream, UnixJsonStreamClient},
+    Result,
+};
+
+/// A transport provided by the user, set using
+/// [`OvsUnixCtl::custom`](crate::OvsUnixCtl::custom).
+pub trait CustomTransport: fmt::Display + Send {
+    /// Connects to the peer and returns the stream messages are exchanged on. This is called
+    /// again on each reconnection.
+    fn connect(&mut self) -> Result<Box<dyn CustomStream>>;
+}
+
+impl fmt::Debug for dyn CustomTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CustomTransport({self})")
+    }
+}
+
+/// Stream of a [`CustomTransport`], exchanging JSON messages with the peer.
+pub trait CustomStream: Send {
+    /// Sends a message to the peer.
+    fn send(&mut self, msg: Value) -> Result<()>;
+
+    /// Receives a single message from the peer (blocking). A timeout must be reported as
+    /// [`Error::Timeout`](crate::Error::Timeout).
+    fn recv(&mut self) -> Result<Value>;
+
+    /// Gets the read and write timeout. No timeout by default.
+    fn timeout(&self) -> Result<Option<Duration>> {
+        Ok(None)
+    }
+
+    /// Sets the read and write timeout. Ignored by default.
+    fn set_timeout(&mut self, _timeout: Option<Duration>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Receives a message from the peer if one is available, without blocking. Not supported by
+    /// default.
+    fn try_recv(&mut self) -> Result<Option<Value>> {
+        Err(crate::Error::Socket(io::ErrorKind::Unsupported.into()))
+    }
+
+    /// Gets the file descriptor of the underlying socket, if any. None by default.
+    fn raw_fd(&self) -> Option<RawFd> {
+        None
+    }
+}
+
+impl fmt::Debug for dyn CustomStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomStream")
+    }
+}
+
+/// One of the supported transports.
+#[derive(Debug)]
+pub(crate) enum Transport {
+    Unix(UnixJsonStreamClient),
+    Tcp(TcpJsonStreamClient),
+    Custom(Box<dyn CustomTransport>),
+}
+
+impl Transport {
+    /// Returns the path to the socket, for Unix transports not using an abstract socket.
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match self {
+            Transport::Unix(client) => client.path(),
+            Transport::Tcp(_) | Transport::Custom(_) => None,
+        }
+    }
+}
+
+impl JsonStreamClient for Transport {
+    type Stream = TransportStream;
+
+    fn connect(&mut self) -> Result<TransportStream> {
+        Ok(match self {
+            Transport::Unix(client) => TransportStream::Unix(client.connect()?),
+            Transport::Tcp(client) => TransportStream::Tcp(client.connect()?),
+            Transport::Custom(client) => TransportStream::Custom(CustomJsonStream {
+                stream: client.connect()?,
+                sent: 0,
+                received: 0,
+            }),
+        })
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Transport::Unix(client) => client.fmt(f),
+            Transport::Tcp(client) => client.fmt(f),
+            Transport::Custom(client) => client.fmt(f),
+        }
+    }
+}
+
+/// Stream of one of the supported transports.
+#[derive(Debug)]
+pub(crate) enum TransportStream {
+    Unix(UnixJsonStream),
+    Tcp(TcpJsonStream),
+    Custom(CustomJsonStream),
+}
+
+/// Stream of a custom transport. As messages are exchanged as JSON values, the number of bytes
+/// is the one of their serialized form.
+#[derive(Debug)]
+pub(crate) struct CustomJsonStream {
+    stream: Box<dyn CustomStream>,
+    sent: u64,
+    received: u64,
+}
+
+impl JsonStream for CustomJsonStream {
+    fn send<M: Serialize>(&mut self, msg: M) -> Result<()> {
+        let msg = serde_json::to_value(msg)?;
+        let len = msg.to_string().len() as u64;
+        self.stream.send(msg)?;
+        self.sent += len;
+        Ok(())
+    }
+
+    fn recv<R>(&mut self) -> Result<R>
+    where
+        R: for<'a> Deserialize<'a>,
+    {
+        // Going through the serialized form, as some types (e.g: raw values) can't be
+        // deserialized from a value.
+        let msg = self.stream.recv()?.to_string();
+        self.received += msg.len() as u64;
+        Ok(serde_json::from_str(&msg)?)
+    }
+
+    fn bytes(&self) -> (u64, u64) {
+        (self.sent, self.received)
+    }
+
+    fn timeout(&self) -> Result<Option<Duration>> {
+        self.stream.timeout()
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.stream.set_timeout(timeout)
+    }
+
+    fn try_recv<R>(&mut self) -> Result<Option<R>>
+    where
+        R: for<'a> Deserialize<'a>,
+    {
+        let Some(msg) = self.stream.try_recv()? else {
+            return Ok(None);
+        };
+        let msg = msg.to_string();
+        self.received += msg.len() as u64;
+        Ok(Some(serde_json::from_str(&msg)?))
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        self.stream.raw_fd()
+    }
+}
+
+impl JsonStream for TransportStream {
+    fn send<M: Serialize>(&mut self, msg: M) -> Result<()> {
+        match self {
+            TransportStream::Unix(stream) => stream.send(msg),
+            TransportStream::Tcp(stream) => stream.send(msg),
+            TransportStream::Custom(stream) => stream.send(msg),
+        }
+    }
+
+    fn recv<R>(&mut self) -> Result<R>
+    where
+        R: for<'a> Deserialize<'a>,
+    {
+        match self {
+            TransportStream::Unix(stream) => stream.recv(),
+            TransportStream::Tcp(stream) => stream.recv(),
+            TransportStream::Custom(stream) => stream.recv(),
+        }
+    }
+
+    fn bytes(&self) -> (u64, u64) {
+        match self {
+            TransportStream::Unix(stream) => stream.bytes(),
+            TransportStream::Tcp(stream) => stream.bytes(),
+            TransportStream::Custom(stream) => stream.bytes(),
+        }
+    }
+
+    fn timeout(&self) -> Result<Option<Duration>> {
+        match self {
+            TransportStream::Unix(stream) => stream.timeout(),
+            TransportStream::Tcp(stream) => stream.timeout(),
+            TransportStream::Custom(stream) => stream.timeout(),
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        match self {
+            TransportStream::Unix(stream) => stream.set_timeout(timeout),
+            TransportStream::Tcp(stream) => stream.set_timeout(timeout),
+            TransportStream::Custom(stream) => stream.set_timeout(timeout),
+        }
+    }
+
+    fn try_recv<R>(&mut self) -> Result<Option<R>>
+    where
+        R: for<'a> Deserialize<'a>,
+    {
+        match self {
+            TransportStream::Unix(stream) => stream.try_recv(),
+            TransportStream::Tcp(stream) => stream.try_recv(),
+            TransportStream::Custom(stream) => stream.try_recv(),
+        }
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        match self {
+            TransportStream::Unix(stream) => stream.raw_fd(),
+            TransportStream::Tcp(stream) => stream.raw_fd(),
+            TransportStream::Custom(stream) => stream.raw_fd(),
+        }
+    }
+}
@@ -1,58 +1,171 @@
 //! Synchronous jsonrpc transport over Unix sockets.
 
 use std::{
-    fmt,
-    os::unix::net::UnixStream,
+    fmt, io,
+    net::Shutdown,
+    os::unix::{
+        io::{AsRawFd, FromRawFd, RawFd},
+        net::UnixStream,
+    },
     path::{Path, PathBuf},
     time::Duration,
 };
 
 use serde::{Deserialize, Serialize};
-use serde_json::Deserializer;
 
 use crate::{
     error::*,
-    jsonrpc::{JsonStream, JsonStreamClient},
+    jsonrpc::{recv_json_pending, send_json, try_recv_json, JsonStream, JsonStreamClient},
     Result,
 };
 
 /// Unix socket transport.
 #[derive(Debug)]
 pub(crate) struct UnixJsonStream {
-    sock: UnixStream,
+    /// The socket, buffered for reading. Bytes read past the end of a message are kept for the
+    /// next one.
+    sock: io::BufReader<UnixStream>,
+    /// Bytes of a message partially received by a non-blocking receive.
+    pending: Vec<u8>,
+    sent: u64,
+    received: u64,
+}
+
+impl UnixJsonStream {
+    pub(crate) fn new(sock: UnixStream) -> UnixJsonStream {
+        UnixJsonStream {
+            sock: io::BufReader::new(sock),
+            pending: Vec::new(),
+            sent: 0,
+            received: 0,
+        }
+    }
+}
+
+impl Drop for UnixJsonStream {
+    fn drop(&mut self) {
+        // Let the peer know about the close right away, even if the socket is shared. Errors,
+        // e.g: the peer closed the connection already, are irrelevant at this point.
+        let _ = self.sock.get_ref().shutdown(Shutdown::Both);
+    }
 }
 
 impl JsonStream for UnixJsonStream {
     fn send<M: Serialize>(&mut self, msg: M) -> Result<()> {
-        Ok(serde_json::to_writer(&self.sock, &msg)?)
+        send_json(self.sock.get_mut(), msg, &mut self.sent)
     }
 
     fn recv<R>(&mut self) -> Result<R>
     where
         R: for<'a> Deserialize<'a>,
     {
-        let resp: R = Deserializer::from_reader(&mut self.sock)
-            .into_iter()
-            .next()
-            .ok_or(Error::Timeout)??;
-        Ok(resp)
+        recv_json_pending(&mut self.sock, &mut self.pending, &mut self.received)
+    }
+
+    fn bytes(&self) -> (u64, u64) {
+        (self.sent, self.received)
+    }
+
+    fn timeout(&self) -> Result<Option<Duration>> {
+        Ok(self.sock.get_ref().read_timeout()?)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.sock.get_ref().set_read_timeout(timeout)?;
+        self.sock.get_ref().set_write_timeout(timeout)?;
+        Ok(())
+    }
+
+    fn try_recv<R>(&mut self) -> Result<Option<R>>
+    where
+        R: for<'a> Deserialize<'a>,
+    {
+        self.sock.get_ref().set_nonblocking(true)?;
+        let res = try_recv_json(&mut self.sock, &mut self.pending, &mut self.received);
+        self.sock.get_ref().set_nonblocking(false)?;
+        res
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        Some(self.sock.get_ref().as_raw_fd())
     }
 }
 
+/// Address of a Unix Domain Socket.
+#[derive(Clone, Debug)]
+enum UnixAddr {
+    /// Socket bound to a path in the filesystem.
+    Path(PathBuf),
+    /// Socket bound to a name in the abstract namespace.
+    #[cfg(target_os = "linux")]
+    Abstract(String),
+    /// Socket connected by someone else and given as a file descriptor. It can't be connected
+    /// again.
+    Fd(RawFd),
+}
+
 #[derive(Debug)]
 pub(crate) struct UnixJsonStreamClient {
-    /// The path to the Unix Domain Socket.
-    path: PathBuf,
+    /// The address of the Unix Domain Socket.
+    addr: UnixAddr,
     /// The read and write timeout to use.
     timeout: Option<Duration>,
+    /// The timeout of the connection to the socket. Without it, connecting is done in place.
+    connect_timeout: Option<Duration>,
+    /// Socket already connected, used by the next connection instead of connecting.
+    stream: Option<UnixStream>,
 }
 
 impl UnixJsonStreamClient {
     /// Creates a new [`UnixJsonStreamClient`] without timeouts to use.
     pub(crate) fn new<P: AsRef<Path>>(path: P) -> UnixJsonStreamClient {
         UnixJsonStreamClient {
-            path: path.as_ref().to_path_buf(),
+            addr: UnixAddr::Path(path.as_ref().to_path_buf()),
+            timeout: None,
+            connect_timeout: None,
+            stream: None,
+        }
+    }
+
+    /// Creates a new [`UnixJsonStreamClient`] connecting to a socket in the abstract namespace,
+    /// without timeouts to use. The name is given without the leading NUL byte.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn new_abstract(name: &str) -> UnixJsonStreamClient {
+        UnixJsonStreamClient {
+            addr: UnixAddr::Abstract(name.to_string()),
             timeout: None,
+            connect_timeout: None,
+            stream: None,
+        }
+    }
+
+    /// Creates a new [`UnixJsonStreamClient`] using the socket the file descriptor refers to
+    /// instead of connecting, without timeouts to use. It takes ownership of the file descriptor.
+    ///
+    /// As the socket can't be connected again, only the first connection succeeds.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptor must be an open and connected Unix stream socket, which isn't used nor
+    /// closed by anything else.
+    pub(crate) unsafe fn from_raw_fd(fd: RawFd) -> UnixJsonStreamClient {
+        UnixJsonStreamClient {
+            addr: UnixAddr::Fd(fd),
+            timeout: None,
+            connect_timeout: None,
+            // SAFETY: guaranteed by the caller.
+            stream: Some(unsafe { UnixStream::from_raw_fd(fd) }),
+        }
+    }
+
+    /// Returns the path to the Unix Domain Socket, `None` for abstract sockets and sockets given
+    /// as a file descriptor.
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match &self.addr {
+            UnixAddr::Path(path) => Some(path),
+            #[cfg(target_os = "linux")]
+            UnixAddr::Abstract(_) => None,
+            UnixAddr::Fd(_) => None,
         }
     }
 
@@ -61,23 +174,196 @@ impl UnixJsonStreamClient {
         self.timeout = Some(timeout);
         self
     }
+
+    /// Sets the connection timeout.
+    pub(crate) fn connect_timeout(mut self, timeout: Duration) -> UnixJsonStreamClient {
+        self.connect_timeout = Some(timeout);
+        self
+    }
 }
 
 impl JsonStreamClient for UnixJsonStreamClient {
     type Stream = UnixJsonStream;
 
     fn connect(&mut self) -> Result<UnixJsonStream> {
-        let sock = UnixStream::connect(&self.path).map_err(Error::Socket)?;
+        let sock = match (self.stream.take(), self.connect_timeout) {
+            (Some(sock), _) => sock,
+            (None, Some(timeout)) => connect_timeout(&self.addr, timeout)?,
+            (None, None) => self.addr.connect().map_err(Error::Socket)?,
+        };
         sock.set_read_timeout(self.timeout).map_err(Error::Socket)?;
         sock.set_write_timeout(self.timeout)
             .map_err(Error::Socket)?;
-        Ok(UnixJsonStream { sock })
+        Ok(UnixJsonStream::new(sock))
+    }
+}
+
+impl UnixAddr {
+    /// Connects to the socket.
+    fn connect(&self) -> io::Result<UnixStream> {
+        match self {
+            UnixAddr::Path(path) => UnixStream::connect(path),
+            #[cfg(target_os = "linux")]
+            UnixAddr::Abstract(name) => {
+                use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+                SocketAddr::from_abstract_name(name)
+                    .and_then(|addr| UnixStream::connect_addr(&addr))
+            }
+            UnixAddr::Fd(_) => Err(not_reconnectable()),
+        }
+    }
+
+    /// Returns the address as the content of `sun_path`: the NUL terminated path, or the name
+    /// following a NUL byte in the abstract namespace.
+    #[cfg(target_os = "linux")]
+    fn sun_path(&self) -> io::Result<Vec<u8>> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let sun_path = match self {
+            UnixAddr::Path(path) => {
+                let path = path.as_os_str().as_bytes();
+                if path.contains(&0) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "socket path contains a NUL byte",
+                    ));
+                }
+                [path, &[0]].concat()
+            }
+            UnixAddr::Abstract(name) => [&[0], name.as_bytes()].concat(),
+            UnixAddr::Fd(_) => return Err(not_reconnectable()),
+        };
+        if sun_path.len() > sys::SUN_PATH_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "socket path too long",
+            ));
+        }
+        Ok(sun_path)
+    }
+}
+
+/// The error of connecting again a socket given as a file descriptor.
+fn not_reconnectable() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotConnected,
+        "cannot connect again a socket given as a file descriptor",
+    )
+}
+
+/// Interval between connection attempts while the listen backlog of the socket is full.
+#[cfg(target_os = "linux")]
+const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Connects to the socket, giving up with [`Error::Timeout`] after the timeout.
+///
+/// Connecting blocks while the listen backlog of the socket is full, e.g: if the daemon is stuck,
+/// and std offers no way to bound it. The connection is made from a non-blocking socket instead,
+/// and retried until the socket makes room for it or the timeout expires.
+#[cfg(target_os = "linux")]
+fn connect_timeout(addr: &UnixAddr, timeout: Duration) -> Result<UnixStream> {
+    use std::{thread, time::Instant};
+
+    let sun_path = addr.sun_path().map_err(Error::Socket)?;
+    let sock = sys::socket_nonblocking().map_err(Error::Socket)?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        match sys::try_connect(&sock, &sun_path) {
+            Ok(()) => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(Error::Timeout);
+                }
+                thread::sleep(CONNECT_RETRY_INTERVAL.min(deadline - now));
+            }
+            Err(e) => return Err(Error::Socket(e)),
+        }
+    }
+    sock.set_nonblocking(false).map_err(Error::Socket)?;
+    Ok(sock)
+}
+
+/// Connects to the socket. Only Linux blocks while the listen backlog of the socket is full,
+/// other systems refuse the connection right away: there is nothing to bound.
+#[cfg(not(target_os = "linux"))]
+fn connect_timeout(addr: &UnixAddr, _timeout: Duration) -> Result<UnixStream> {
+    addr.connect().map_err(Error::Socket)
+}
+
+/// Bindings of the few socket calls std doesn't expose, to connect without blocking.
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::{
+        ffi::c_int,
+        io, mem,
+        os::unix::{
+            io::{AsRawFd, FromRawFd},
+            net::UnixStream,
+        },
+    };
+
+    const AF_UNIX: c_int = 1;
+    #[cfg(not(any(target_arch = "mips", target_arch = "mips64")))]
+    const SOCK_STREAM: c_int = 1;
+    #[cfg(any(target_arch = "mips", target_arch = "mips64"))]
+    const SOCK_STREAM: c_int = 2;
+    const SOCK_CLOEXEC: c_int = 0o2000000;
+
+    /// The `sun_path` size of `struct sockaddr_un`.
+    pub(super) const SUN_PATH_LEN: usize = 108;
+
+    #[repr(C)]
+    struct SockaddrUn {
+        sun_family: u16,
+        sun_path: [u8; SUN_PATH_LEN],
+    }
+
+    extern "C" {
+        fn socket(domain: c_int, ty: c_int, protocol: c_int) -> c_int;
+        fn connect(fd: c_int, addr: *const SockaddrUn, len: u32) -> c_int;
+    }
+
+    /// Creates a Unix stream socket, not connected yet, in non-blocking mode.
+    pub(super) fn socket_nonblocking() -> io::Result<UnixStream> {
+        // SAFETY: no memory is involved, the result is checked.
+        let fd = unsafe { socket(AF_UNIX, SOCK_STREAM | SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the file descriptor was just created and isn't owned by anything else.
+        let sock = unsafe { UnixStream::from_raw_fd(fd) };
+        sock.set_nonblocking(true)?;
+        Ok(sock)
+    }
+
+    /// Connects the socket to the address given as the content of `sun_path`, which must not
+    /// exceed [`SUN_PATH_LEN`]. In non-blocking mode, it fails with
+    /// [`io::ErrorKind::WouldBlock`] while the listen backlog of the socket is full.
+    pub(super) fn try_connect(sock: &UnixStream, sun_path: &[u8]) -> io::Result<()> {
+        let mut addr = SockaddrUn {
+            sun_family: AF_UNIX as u16,
+            sun_path: [0; SUN_PATH_LEN],
+        };
+        addr.sun_path[..sun_path.len()].copy_from_slice(sun_path);
+        let len = mem::size_of::<u16>() + sun_path.len();
+        // SAFETY: the address is valid and initialized over its given length.
+        match unsafe { connect(sock.as_raw_fd(), &addr, len as u32) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
     }
 }
 
 impl fmt::Display for UnixJsonStreamClient {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(f, "unix://{}", self.path.to_string_lossy())
+        match &self.addr {
+            UnixAddr::Path(path) => write!(f, "unix://{}", path.to_string_lossy()),
+            #[cfg(target_os = "linux")]
+            UnixAddr::Abstract(name) => write!(f, "unix-abstract://{name}"),
+            UnixAddr::Fd(fd) => write!(f, "unix-fd://{fd}"),
+        }
     }
 }
 
@@ -140,7 +426,7 @@ mod tests {
         // Fake server
         let (sock, _) = server.accept().unwrap();
         sock.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
-        let mut stream = UnixJsonStream { sock };
+        let mut stream = UnixJsonStream::new(sock);
         for _n in 1..5 {
             let request: ReceiveRequest = stream.recv().unwrap();
             if request.method == "ping" {
@@ -173,4 +459,111 @@ mod tests {
         // Clean up
         fs::remove_file(&socket_path).unwrap();
     }
+
+    #[test]
+    fn buffered_messages() {
+        let (mut peer, sock) = UnixStream::pair().unwrap();
+        let mut stream = UnixJsonStream::new(sock);
+
+        // Both messages are read at once, the second one must be kept for the next call.
+        io::Write::write_all(&mut peer, br#"{"id": 1} {"id": 2}"#).unwrap();
+        for id in [1, 2] {
+            let msg: serde_json::Value = stream.recv().unwrap();
+            assert_eq!(msg["id"], id);
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn abstract_socket() {
+        use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+
+        let name = format!("ovs-unixctl-test-{}", process::id());
+        let server =
+            UnixListener::bind_addr(&SocketAddr::from_abstract_name(&name).unwrap()).unwrap();
+        let server_thread = thread::spawn(move || {
+            let (sock, _) = server.accept().unwrap();
+            let mut stream = UnixJsonStream::new(sock);
+            let request: serde_json::Value = stream.recv().unwrap();
+            stream
+                .send(serde_json::json!({"result": "pong", "error": null, "id": request["id"]}))
+                .unwrap();
+        });
+
+        let stream_client = UnixJsonStreamClient::new_abstract(&name);
+        assert_eq!(
+            format!("{}", stream_client),
+            format!("unix-abstract://{name}")
+        );
+        assert!(stream_client.path().is_none());
+
+        let mut client = jsonrpc::Client::new(stream_client).unwrap();
+        let response: jsonrpc::Response<String> = client.call("ping").unwrap();
+        assert_eq!(response.result.unwrap(), "pong");
+        server_thread.join().unwrap();
+    }
+
+    #[test]
+    fn connect_timeout() {
+        let socket_path = std::env::temp_dir().join(format!(
+            "ovs-unixctl-connect-timeout-{}.socket",
+            process::id()
+        ));
+        let _ = fs::remove_file(&socket_path);
+        let server = UnixListener::bind(&socket_path).unwrap();
+        let mut stream_client =
+            UnixJsonStreamClient::new(&socket_path).connect_timeout(Duration::from_secs(1));
+        let res = stream_client.connect();
+        drop(server);
+        fs::remove_file(&socket_path).unwrap();
+        assert!(res.is_ok());
+
+        // Nothing listens anymore.
+        let res = stream_client.connect();
+        assert!(matches!(res, Err(Error::Socket(_))));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn connect_timeout_backlog_full() {
+        use std::{
+            os::{fd::AsRawFd, linux::net::SocketAddrExt, unix::net::SocketAddr},
+            time::Instant,
+        };
+
+        extern "C" {
+            fn listen(fd: std::ffi::c_int, backlog: std::ffi::c_int) -> std::ffi::c_int;
+        }
+
+        let name = format!("ovs-unixctl-backlog-{}", process::id());
+        let server =
+            UnixListener::bind_addr(&SocketAddr::from_abstract_name(&name).unwrap()).unwrap();
+        // SAFETY: the socket is open and already listening, only its backlog is changed.
+        assert_eq!(unsafe { listen(server.as_raw_fd(), 0) }, 0);
+
+        let mut stream_client =
+            UnixJsonStreamClient::new_abstract(&name).connect_timeout(Duration::from_millis(50));
+        // The first connection fills the backlog, as the server never accepts it.
+        let _first = stream_client.connect().unwrap();
+        let start = Instant::now();
+        assert!(matches!(stream_client.connect(), Err(Error::Timeout)));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // Room is made once the pending connection is accepted.
+        let _accepted = server.accept().unwrap();
+        assert!(stream_client.connect().is_ok());
+    }
+
+    #[test]
+    fn shutdown_on_drop() {
+        use std::io::Read;
+
+        let (sock, mut peer) = UnixStream::pair().unwrap();
+        // The socket outlives the stream, but the peer must see the connection closed anyway.
+        let _clone = sock.try_clone().unwrap();
+        drop(UnixJsonStream::new(sock));
+
+        peer.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        assert_eq!(peer.read(&mut [0; 1]).unwrap(), 0);
+    }
 }
@@ -0,0 +1,109 @@
+//! Logging (vlog) helpers.
+
+use std::{fmt, str::FromStr};
+
+use crate::{error::Error, parse, OvsUnixCtl, Result};
+
+/// An OVS log level, from the least to the most verbose.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum VlogLevel {
+    /// Logging is disabled.
+    Off,
+    /// Emergencies only.
+    Emer,
+    /// Errors.
+    Err,
+    /// Warnings.
+    Warn,
+    /// Informational messages.
+    #[default]
+    Info,
+    /// Debug messages.
+    Dbg,
+}
+
+impl FromStr for VlogLevel {
+    type Err = Error;
+
+    /// Parses a log level, case-insensitively as OVS does, e.g: `DBG` or `dbg`.
+    fn from_str(s: &str) -> Result<VlogLevel> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "off" => VlogLevel::Off,
+            "emer" => VlogLevel::Emer,
+            "err" => VlogLevel::Err,
+            "warn" => VlogLevel::Warn,
+            "info" => VlogLevel::Info,
+            "dbg" => VlogLevel::Dbg,
+            _ => return Err(Error::InvalidArgument(format!("invalid log level {s}"))),
+        })
+    }
+}
+
+impl fmt::Display for VlogLevel {
+    /// Formats the log level as OVS prints it, e.g: `DBG`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VlogLevel::Off => "OFF",
+            VlogLevel::Emer => "EMER",
+            VlogLevel::Err => "ERR",
+            VlogLevel::Warn => "WARN",
+            VlogLevel::Info => "INFO",
+            VlogLevel::Dbg => "DBG",
+        })
+    }
+}
+
+/// A logging module and its log level for each destination.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VlogModule {
+    /// Name of the module.
+    pub name: String,
+    /// Level of the messages logged to the console.
+    pub console: VlogLevel,
+    /// Level of the messages logged to syslog.
+    pub syslog: VlogLevel,
+    /// Level of the messages logged to the log file.
+    pub file: VlogLevel,
+}
+
+impl OvsUnixCtl {
+    /// Runs the "vlog/list" command and returns the logging modules and their log levels.
+    pub fn vlog_list(&mut self) -> Result<Vec<VlogModule>> {
+        parse::vlog::list(&self.run_output("vlog/list", None)?)
+    }
+
+    /// Runs the "vlog/set" command with the given spec, e.g: `unixctl:file:dbg`, to change log
+    /// levels. The spec is passed as is, see ovs-appctl(8) for its syntax.
+    pub fn vlog_set(&mut self, spec: &str) -> Result<()> {
+        self.run("vlog/set", Some(&[spec]))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vlog_level() {
+        for level in [
+            VlogLevel::Off,
+            VlogLevel::Emer,
+            VlogLevel::Err,
+            VlogLevel::Warn,
+            VlogLevel::Info,
+            VlogLevel::Dbg,
+        ] {
+            assert_eq!(level.to_string().parse::<VlogLevel>().unwrap(), level);
+        }
+        assert_eq!("dbg".parse::<VlogLevel>().unwrap(), VlogLevel::Dbg);
+        assert!(VlogLevel::Warn < VlogLevel::Dbg);
+        assert!(matches!(
+            "debug".parse::<VlogLevel>(),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}